@@ -23,11 +23,13 @@ use im_online::sr25519::AuthorityId as ImOnlineId;
 use primitives::{Pair, Public, crypto::UncheckedInto};
 use node_runtime::{
     GenesisConfig, SystemConfig, SessionConfig, BabeConfig, StakingConfig,
-    IndicesConfig, ImOnlineConfig, BalancesConfig, GrandpaConfig, SudoConfig,
-    AuthorityDiscoveryConfig, SessionKeys, Perbill, StakerStatus, WASM_BINARY,
+    IndicesConfig, ImOnlineConfig, BalancesConfig, GrandpaConfig, DemocracyConfig,
+    CouncilConfig, TechnicalCommitteeConfig, AuthorityDiscoveryConfig, ContractsConfig,
+    RewardsConfig, BlockTimeConfig, RobonomicsConfig,
+    SessionKeys, Perbill, StakerStatus, Treasury, WASM_BINARY,
 };
-use node_runtime::constants::currency::*;
-use node_runtime::types::{AccountId, Balance};
+use node_runtime::constants::{currency::*, time::*};
+use node_runtime::types::{AccountId, Balance, Moment};
 use substrate_service::{self, Properties};
 use serde_json::json;
 use hex_literal::hex;
@@ -35,6 +37,9 @@ use telemetry::TelemetryEndpoints;
 
 const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// Short slot duration of development chains for low latency robot control.
+const DEV_SLOT_DURATION: Moment = 2000;
+
 /// Specialised `ChainSpec`. This is a specialisation of the general Substrate ChainSpec type.
 pub type ChainSpec = substrate_service::ChainSpec<GenesisConfig>;
 
@@ -94,9 +99,15 @@ fn session_keys(grandpa: GrandpaId, babe: BabeId, im_online: ImOnlineId) -> Sess
 }
 
 /// Helper function to create GenesisConfig for testing
+///
+/// Council and technical committee default to initial authorities controllers.
 pub fn testnet_genesis(
     initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId)>,
     endowed_accounts: Option<Vec<AccountId>>,
+    council_members: Option<Vec<AccountId>>,
+    technical_committee: Option<Vec<AccountId>>,
+    enable_println: bool,
+    slot_duration: Moment,
 ) -> GenesisConfig {
     let endowed_accounts: Vec<AccountId> = endowed_accounts.unwrap_or_else(|| {
         vec![
@@ -115,6 +126,10 @@ pub fn testnet_genesis(
         ]
     });
 
+    let controllers: Vec<AccountId> = initial_authorities.iter().map(|x| x.1.clone()).collect();
+    let council_members = council_members.unwrap_or_else(|| controllers.clone());
+    let technical_committee = technical_committee.unwrap_or(controllers);
+
     const ENDOWMENT: Balance = 100 * XRT;
     const STASH: Balance = 1_000 * XRT;
 
@@ -123,6 +138,10 @@ pub fn testnet_genesis(
             code: WASM_BINARY.to_vec(),
             changes_trie_config: Default::default(),
         }),
+        block_time: Some(BlockTimeConfig {
+            slot_duration,
+            epoch_duration: EPOCH_DURATION_IN_SLOTS,
+        }),
         indices: Some(IndicesConfig {
             ids: endowed_accounts.iter().cloned()
                 .chain(initial_authorities.iter().map(|x| x.0.clone()))
@@ -132,6 +151,8 @@ pub fn testnet_genesis(
             balances: endowed_accounts.iter().cloned()
                 .map(|k| (k, ENDOWMENT))
                 .chain(initial_authorities.iter().map(|x| (x.0.clone(), STASH)))
+                // Treasury should exist to collect protocol fees smaller than existential deposit
+                .chain(Some((Treasury::account_id(), ENDOWMENT)))
                 .collect(),
             vesting: vec![],
         }),
@@ -151,8 +172,26 @@ pub fn testnet_genesis(
             slash_reward_fraction: Perbill::from_percent(10),
             .. Default::default()
         }),
-        sudo: Some(SudoConfig {
-            key: endowed_accounts[0].clone(),
+        rewards: Some(RewardsConfig {
+            ideal_stake: Perbill::from_percent(50),
+            min_inflation: Perbill::from_percent(2),
+            max_inflation: Perbill::from_percent(10),
+        }),
+        democracy: Some(DemocracyConfig::default()),
+        collective_Instance1: Some(CouncilConfig {
+            members: council_members,
+            phantom: Default::default(),
+        }),
+        collective_Instance2: Some(TechnicalCommitteeConfig {
+            members: technical_committee,
+            phantom: Default::default(),
+        }),
+        contracts: Some(ContractsConfig {
+            current_schedule: contracts::Schedule {
+                enable_println, // this should only be enabled on development chains
+                ..Default::default()
+            },
+            gas_price: 1 * COASE,
         }),
         babe: Some(BabeConfig {
             authorities: vec![], 
@@ -166,21 +205,20 @@ pub fn testnet_genesis(
         authority_discovery: Some(AuthorityDiscoveryConfig{
             keys: vec![],
         }),
+        robonomics: Some(RobonomicsConfig {
+            liabilities: vec![],
+            demands: vec![],
+            offers: vec![],
+        }),
     }
 }
 
-/// Robonomics testnet config. 
-pub fn robonomics_testnet_config() -> ChainSpec {
-    ChainSpec::from_json_bytes(&include_bytes!("../../res/robonomics_testnet.json")[..]).unwrap()
-}
-
 /// XRT token properties.
 fn xrt_props() -> Properties {
     json!({"tokenDecimals": 9, "tokenSymbol": "XRT"}).as_object().unwrap().clone()
 }
 
-/*
-/// Robonomics testnet config. 
+/// Robonomics testnet genesis.
 fn robonomics_config_genesis() -> GenesisConfig {
     let initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId)> = vec![(
         // validator-01 
@@ -211,7 +249,11 @@ fn robonomics_config_genesis() -> GenesisConfig {
         ];
     testnet_genesis(
         initial_authorities,
+        Some(endowed_accounts.clone()),
+        Some(endowed_accounts.clone()),
         Some(endowed_accounts),
+        false,
+        SLOT_DURATION,
     )
 }
 
@@ -238,7 +280,6 @@ pub fn robonomics_testnet_config() -> ChainSpec {
         Some(xrt_props())
     )
 }
-*/
 
 fn development_config_genesis() -> GenesisConfig {
     testnet_genesis(
@@ -246,6 +287,10 @@ fn development_config_genesis() -> GenesisConfig {
             get_authority_keys_from_seed("Alice"),
         ],
         None,
+        None,
+        None,
+        true,
+        DEV_SLOT_DURATION,
     )
 }
 
@@ -270,6 +315,10 @@ fn local_testnet_genesis() -> GenesisConfig {
             get_authority_keys_from_seed("Bob"),
         ],
         None,
+        None,
+        None,
+        false,
+        DEV_SLOT_DURATION,
     )
 }
 
@@ -0,0 +1,699 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Robonomics subcommands: send demands, offers and results, inspect, export and import liabilities.
+//! Extrinsics are signed locally and submitted to node over HTTP JSON-RPC.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::{env, thread, time::Duration};
+use structopt::StructOpt;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use codec::{Encode, Decode};
+use tokio::runtime::Runtime;
+use jsonrpc_core_client::{RawClient, transports::http};
+use jsonrpc_core::Params;
+use substrate_cli::{GetLogFilter, error};
+use primitives::{
+    Bytes, H256, blake2_256, twox_128, sr25519,
+    crypto::{Pair, Ss58Codec, KeyTypeId, key_types::ACCOUNT},
+    storage::StorageKey,
+};
+use sr_primitives::generic::Era;
+use node_runtime::{
+    Call, Event, EventRecord, RobonomicsCall, UncheckedExtrinsic, SignedExtra,
+    robonomics::{self, Capacity, Liability, CheckRateLimit, CheckLiabilityParty, RawEvent as RobonomicsEvent},
+    sponsorship::TakeSponsoredFees,
+    types::{AccountId, Balance, BlockNumber, Hash, Index, key_types},
+};
+use crate::chain_spec::ChainSpec;
+use crate::snapshot::{self, Snapshot};
+
+/// Robonomics node subcommands.
+#[derive(Debug, StructOpt, Clone)]
+pub enum CustomSubcommands {
+    /// Send demand for service.
+    #[structopt(name = "demand")]
+    Demand(DemandCmd),
+
+    /// Send offer of service.
+    #[structopt(name = "offer")]
+    Offer(OfferCmd),
+
+    /// Send result to finalize liability.
+    #[structopt(name = "finalize")]
+    Finalize(FinalizeCmd),
+
+    /// Inspect liabilities.
+    #[structopt(name = "liability")]
+    Liability(LiabilityCmd),
+
+    /// Manage node keys.
+    #[structopt(name = "key")]
+    Key(KeyCmd),
+
+    /// Export liabilities and open orders at given block.
+    #[structopt(name = "export-liabilities")]
+    ExportLiabilities(ExportLiabilitiesCmd),
+
+    /// Seed robonomics genesis of chain spec by exported liabilities.
+    #[structopt(name = "import-liabilities")]
+    ImportLiabilities(ImportLiabilitiesCmd),
+}
+
+impl GetLogFilter for CustomSubcommands {
+    fn get_log_filter(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Environment variable with password of secret URI or keystore.
+const PASSWORD_ENV: &str = "ROBONOMICS_PASSWORD";
+
+/// How many blocks to wait for inclusion of submitted extrinsic.
+const INCLUSION_TIMEOUT: BlockNumber = 20;
+
+/// Node connection parameters.
+#[derive(Debug, StructOpt, Clone)]
+pub struct NodeParams {
+    /// Node HTTP JSON-RPC endpoint.
+    #[structopt(long = "url", default_value = "http://localhost:9933")]
+    pub url: String,
+}
+
+/// Extrinsic signer parameters: secret URI or key from keystore.
+#[derive(Debug, StructOpt, Clone)]
+pub struct SignerParams {
+    /// Secret URI of signer account, e.g. `//Alice` or mnemonic phrase.
+    #[structopt(long = "suri", value_name = "SECRET_URI")]
+    pub suri: Option<String>,
+
+    /// Keystore directory of signer account key.
+    #[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+    pub keystore_path: Option<PathBuf>,
+
+    /// Signer account address, its `robo` key is read from keystore, account key is used
+    /// when `robo` key isn't found.
+    #[structopt(long = "account", value_name = "ADDRESS")]
+    pub account: Option<String>,
+
+    #[structopt(flatten)]
+    pub password: PasswordParams,
+}
+
+/// Password parameters, password is never passed as command line argument.
+#[derive(Debug, StructOpt, Clone)]
+pub struct PasswordParams {
+    /// Prompt for password of secret URI or keystore, `ROBONOMICS_PASSWORD` environment
+    /// variable is used otherwise.
+    #[structopt(long = "password-interactive")]
+    pub interactive: bool,
+}
+
+/// Order parameters.
+#[derive(Debug, StructOpt, Clone)]
+pub struct OrderParams {
+    /// Model (base58 encoded).
+    #[structopt(long = "model")]
+    pub model: String,
+
+    /// Objective (base58 encoded).
+    #[structopt(long = "objective")]
+    pub objective: String,
+
+    /// Cost in the smallest token units.
+    #[structopt(long = "cost")]
+    pub cost: Balance,
+
+    /// Custodian account address.
+    #[structopt(long = "custodian", value_name = "ADDRESS")]
+    pub custodian: Option<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct DemandCmd {
+    #[structopt(flatten)]
+    pub order: OrderParams,
+    #[structopt(flatten)]
+    pub signer: SignerParams,
+    #[structopt(flatten)]
+    pub node: NodeParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct OfferCmd {
+    #[structopt(flatten)]
+    pub order: OrderParams,
+
+    /// How many times offer could be matched, zero means unlimited.
+    #[structopt(long = "capacity", default_value = "1")]
+    pub capacity: u32,
+
+    /// Offer validity in blocks.
+    #[structopt(long = "validity")]
+    pub validity: Option<BlockNumber>,
+
+    #[structopt(flatten)]
+    pub signer: SignerParams,
+    #[structopt(flatten)]
+    pub node: NodeParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct FinalizeCmd {
+    /// Liability index.
+    pub index: u64,
+
+    /// Result (base58 encoded).
+    pub result: String,
+
+    #[structopt(flatten)]
+    pub signer: SignerParams,
+    #[structopt(flatten)]
+    pub node: NodeParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum LiabilityCmd {
+    /// Show liability by index.
+    #[structopt(name = "show")]
+    Show {
+        /// Liability index.
+        index: u64,
+        #[structopt(flatten)]
+        node: NodeParams,
+    },
+
+    /// List all liabilities.
+    #[structopt(name = "list")]
+    List {
+        #[structopt(flatten)]
+        node: NodeParams,
+    },
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum KeyCmd {
+    /// Insert key into node keystore, `robo` key is used by ROS bridge.
+    #[structopt(name = "insert")]
+    Insert(KeyInsertCmd),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeyInsertCmd {
+    /// Secret URI of the key.
+    #[structopt(long = "suri", value_name = "SECRET_URI")]
+    pub suri: String,
+
+    /// Key type, four ASCII characters.
+    #[structopt(long = "key-type", default_value = "robo")]
+    pub key_type: String,
+
+    /// Keystore directory, key is inserted by node RPC when not given.
+    #[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+    pub keystore_path: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub password: PasswordParams,
+
+    #[structopt(flatten)]
+    pub node: NodeParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportLiabilitiesCmd {
+    /// Block number, best block when not given.
+    #[structopt(long = "at", value_name = "BLOCK")]
+    pub at: Option<BlockNumber>,
+
+    /// Output format: `json` or `csv`.
+    #[structopt(long = "format", default_value = "json")]
+    pub format: String,
+
+    /// Output file, stdout when not given.
+    #[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub node: NodeParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct ImportLiabilitiesCmd {
+    /// Snapshot file written by `export-liabilities --format json`.
+    #[structopt(long = "input", value_name = "PATH", parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Chain spec to seed: `dev`, `local`, `robonomics` or path to non-raw JSON chain spec.
+    #[structopt(long = "chain", value_name = "CHAIN_SPEC")]
+    pub chain: String,
+
+    /// Output file of chain spec, stdout when not given.
+    #[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+impl CustomSubcommands {
+    /// Run subcommand.
+    pub fn run(self) -> error::Result<()> {
+        match self {
+            CustomSubcommands::Demand(cmd) => {
+                let (model, objective, cost, custodian) = cmd.order.decode()?;
+                let call = RobonomicsCall::demand(model, objective, cost, custodian);
+                submit(&cmd.node, &cmd.signer, call)
+            },
+            CustomSubcommands::Offer(cmd) => {
+                let (model, objective, cost, custodian) = cmd.order.decode()?;
+                let capacity = match cmd.capacity {
+                    0 => Capacity::Unlimited,
+                    n => Capacity::Limited(n),
+                };
+                let call = RobonomicsCall::offer(model, objective, cost, custodian, capacity, cmd.validity);
+                submit(&cmd.node, &cmd.signer, call)
+            },
+            CustomSubcommands::Finalize(cmd) => {
+                let result = decode_bs58(&cmd.result)?;
+                let call = RobonomicsCall::finalize(cmd.index, result);
+                submit(&cmd.node, &cmd.signer, call)
+            },
+            CustomSubcommands::Liability(LiabilityCmd::Show { index, node }) => {
+                let mut rpc = Rpc::connect(&node.url)?;
+                match rpc.liability(index, None)? {
+                    Some(liability) => print_liability(index, &liability),
+                    None => println!("Liability {} not found", index),
+                }
+                Ok(())
+            },
+            CustomSubcommands::Key(KeyCmd::Insert(cmd)) => cmd.run(),
+            CustomSubcommands::ExportLiabilities(cmd) => cmd.run(),
+            CustomSubcommands::ImportLiabilities(cmd) => cmd.run(),
+            CustomSubcommands::Liability(LiabilityCmd::List { node }) => {
+                let mut rpc = Rpc::connect(&node.url)?;
+                let count: u64 = rpc.storage(&twox_128(b"Robonomics LiabilityCount"))?.unwrap_or(0);
+                for index in 0..count {
+                    if let Some(liability) = rpc.liability(index, None)? {
+                        print_liability(index, &liability);
+                    }
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl KeyInsertCmd {
+    fn run(self) -> error::Result<()> {
+        let key_type = parse_key_type(&self.key_type)?;
+        let pair = sr25519::Pair::from_string(&self.suri, None)
+            .map_err(|e| error::Error::Input(format!("Invalid secret URI: {:?}", e)))?;
+
+        match self.keystore_path {
+            Some(path) => {
+                let store = keystore::Store::open(path, self.password.read()?.map(Into::into))
+                    .map_err(|e| error::Error::Input(format!("Unable to open keystore: {:?}", e)))?;
+                store.write().insert_by_type::<sr25519::Pair>(key_type, &self.suri)
+                    .map_err(|e| error::Error::Input(format!("Unable to insert key: {:?}", e)))?;
+            },
+            None => {
+                let mut rpc = Rpc::connect(&self.node.url)?;
+                let _: Bytes = rpc.call("author_insertKey", vec![
+                    json!(self.key_type),
+                    json!(self.suri),
+                    json!(Bytes(pair.public().as_ref().to_vec())),
+                ])?;
+            },
+        }
+
+        println!("Key {} inserted: {}", self.key_type, pair.public().to_ss58check());
+        Ok(())
+    }
+}
+
+impl ExportLiabilitiesCmd {
+    fn run(self) -> error::Result<()> {
+        let csv = match self.format.as_str() {
+            "json" => false,
+            "csv" => true,
+            format => return Err(error::Error::Input(format!("Unknown format {}, use json or csv", format))),
+        };
+
+        let mut rpc = Rpc::connect(&self.node.url)?;
+        let block = match self.at {
+            Some(block) => block,
+            None => rpc.best_number()?,
+        };
+        let block_hash = rpc.block_hash(block)?;
+
+        let snapshot = Snapshot::new(block, block_hash, rpc.snapshot(block_hash)?);
+
+        let out: Box<dyn Write> = match self.output {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+        if csv {
+            snapshot.write_csv(out)
+        } else {
+            snapshot.write_json(out)
+        }
+    }
+}
+
+impl ImportLiabilitiesCmd {
+    fn run(self) -> error::Result<()> {
+        let snapshot: Snapshot = serde_json::from_reader(File::open(&self.input)?)
+            .map_err(|e| error::Error::Input(format!("Unable to read snapshot: {:?}", e)))?;
+        let config = snapshot.genesis_config()?;
+
+        let spec = match crate::load_spec(&self.chain).map_err(error::Error::Input)? {
+            Some(spec) => spec,
+            None => ChainSpec::from_json_file(PathBuf::from(&self.chain)).map_err(error::Error::Input)?,
+        };
+        let mut spec: Value = serde_json::from_str(&spec.to_json(false).map_err(error::Error::Input)?)
+            .map_err(|e| error::Error::Input(format!("Unable to parse chain spec: {:?}", e)))?;
+
+        let balances: Vec<(AccountId, Balance)> = match spec.pointer("/genesis/runtime/balances/balances") {
+            Some(balances) => serde_json::from_value(balances.clone())
+                .map_err(|e| error::Error::Input(format!("Unable to parse genesis balances: {:?}", e)))?,
+            None => vec![],
+        };
+        snapshot::check_reserves(&config, &balances)?;
+
+        let config = serde_json::to_value(config)
+            .map_err(|e| error::Error::Input(format!("Unable to encode robonomics genesis: {:?}", e)))?;
+        match spec.pointer_mut("/genesis/runtime") {
+            Some(runtime) => runtime["robonomics"] = config,
+            None => return Err(error::Error::Input("Raw chain spec could not be seeded".into())),
+        }
+
+        let out: Box<dyn Write> = match self.output {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+        serde_json::to_writer_pretty(out, &spec)
+            .map_err(|e| error::Error::Input(format!("Unable to write chain spec: {:?}", e)))
+    }
+}
+
+impl OrderParams {
+    fn decode(&self) -> error::Result<(Vec<u8>, Vec<u8>, Balance, Option<AccountId>)> {
+        let custodian = match self.custodian {
+            Some(ref address) => Some(decode_account(address)?),
+            None => None,
+        };
+        Ok((decode_bs58(&self.model)?, decode_bs58(&self.objective)?, self.cost, custodian))
+    }
+}
+
+impl SignerParams {
+    /// Signer key pair given by secret URI or read from keystore.
+    fn pair(&self) -> error::Result<sr25519::Pair> {
+        let password = self.password.read()?;
+        match (&self.suri, &self.keystore_path, &self.account) {
+            (Some(suri), _, _) => sr25519::Pair::from_string(suri, password.as_ref().map(String::as_str))
+                .map_err(|e| error::Error::Input(format!("Invalid secret URI: {:?}", e))),
+            (None, Some(path), Some(account)) => {
+                let public = decode_account(account)?;
+                let store = keystore::Store::open(path.clone(), password.map(Into::into))
+                    .map_err(|e| error::Error::Input(format!("Unable to open keystore: {:?}", e)))?;
+                let store = store.read();
+                let pair = store.key_pair_by_type::<sr25519::Pair>(&public, key_types::ROBO)
+                    .or_else(|_| store.key_pair_by_type::<sr25519::Pair>(&public, ACCOUNT))
+                    .map_err(|e| error::Error::Input(format!("Unable to read key from keystore: {:?}", e)))?;
+                Ok(pair)
+            },
+            _ => Err(error::Error::Input("Signer is required: use --suri or --keystore-path with --account".into())),
+        }
+    }
+}
+
+impl PasswordParams {
+    /// Password prompted from terminal or read from environment.
+    fn read(&self) -> error::Result<Option<String>> {
+        if self.interactive {
+            return Ok(Some(rpassword::read_password_from_tty(Some("Password: "))?));
+        }
+        Ok(env::var(PASSWORD_ENV).ok())
+    }
+}
+
+/// Blocking JSON-RPC client.
+struct Rpc {
+    runtime: Runtime,
+    client: RawClient,
+}
+
+impl Rpc {
+    fn connect(url: &str) -> error::Result<Self> {
+        let mut runtime = Runtime::new()?;
+        let client = runtime.block_on(http::connect::<RawClient>(url))
+            .map_err(|e| error::Error::Input(format!("Unable to connect node: {:?}", e)))?;
+        Ok(Rpc { runtime, client })
+    }
+
+    fn call<T: DeserializeOwned>(&mut self, method: &str, params: Vec<Value>) -> error::Result<T> {
+        let value = self.runtime.block_on(self.client.call_method(method, Params::Array(params)))
+            .map_err(|e| error::Error::Input(format!("RPC call {} failed: {:?}", method, e)))?;
+        serde_json::from_value(value)
+            .map_err(|e| error::Error::Input(format!("Bad RPC response of {}: {:?}", method, e)))
+    }
+
+    /// Read and decode storage value by key at best block.
+    fn storage<T: Decode>(&mut self, key: &[u8]) -> error::Result<Option<T>> {
+        self.storage_at(key, None)
+    }
+
+    /// Read and decode storage value by key at given block.
+    fn storage_at<T: Decode>(&mut self, key: &[u8], at: Option<H256>) -> error::Result<Option<T>> {
+        let key = json!(StorageKey(key.to_vec()));
+        let data: Option<Bytes> = self.call("state_getStorage", vec![key, json!(at)])?;
+        match data {
+            Some(data) => T::decode(&mut &data[..])
+                .map(Some)
+                .map_err(|_| error::Error::Input("Unable to decode storage value".into())),
+            None => Ok(None),
+        }
+    }
+
+    fn liability(&mut self, index: u64, at: Option<H256>) -> error::Result<Option<Liability<Balance, AccountId>>> {
+        let key = [&b"Robonomics LiabilityOf"[..], &index.encode()[..]].concat();
+        self.storage_at(&blake2_256(&key), at)
+    }
+
+    /// Hashes of orders waiting for matching, `OrdersOf` linked map is walked from its head.
+    fn open_order_hashes(&mut self, at: H256) -> error::Result<Vec<Hash>> {
+        let mut hashes = vec![];
+        let mut next: Option<Vec<u8>> = self.storage_at(&blake2_256(b"head of Robonomics OrdersOf"), Some(at))?;
+        while let Some(model) = next {
+            let key = [&b"Robonomics OrdersOf"[..], &model.encode()[..]].concat();
+            // Linked map value is followed by linkage: previous and next keys
+            let (model_hashes, (_, next_model)): (Vec<Hash>, (Option<Vec<u8>>, Option<Vec<u8>>)) = self
+                .storage_at(&blake2_256(&key), Some(at))?
+                .ok_or_else(|| error::Error::Input("Broken OrdersOf linked map".into()))?;
+            hashes.extend(model_hashes);
+            next = next_model;
+        }
+        Ok(hashes)
+    }
+
+    /// Liabilities and orders waiting for matching read from storage at given block.
+    fn snapshot(&mut self, at: H256) -> error::Result<robonomics::Snapshot<Balance, AccountId, BlockNumber, Hash>> {
+        let liability_count: u64 = self.storage_at(&twox_128(b"Robonomics LiabilityCount"), Some(at))?.unwrap_or(0);
+        let mut liabilities = vec![];
+        for index in 0..liability_count {
+            if let Some(liability) = self.liability(index, Some(at))? {
+                liabilities.push((index, liability));
+            }
+        }
+
+        let mut demands = vec![];
+        let mut offers = vec![];
+        for order_hash in self.open_order_hashes(at)? {
+            let key = [&b"Robonomics DemandOf"[..], &order_hash.encode()[..]].concat();
+            let order_demands: Vec<_> = self.storage_at(&blake2_256(&key), Some(at))?.unwrap_or_default();
+            if !order_demands.is_empty() {
+                demands.push((order_hash, order_demands));
+            }
+            let key = [&b"Robonomics OfferOf"[..], &order_hash.encode()[..]].concat();
+            let order_offers: Vec<_> = self.storage_at(&blake2_256(&key), Some(at))?.unwrap_or_default();
+            if !order_offers.is_empty() {
+                offers.push((order_hash, order_offers));
+            }
+        }
+
+        Ok(robonomics::Snapshot { liability_count, liabilities, demands, offers })
+    }
+
+    fn account_nonce(&mut self, account: &AccountId) -> error::Result<Index> {
+        let key = [&b"System AccountNonce"[..], &account.encode()[..]].concat();
+        Ok(self.storage(&blake2_256(&key))?.unwrap_or(0))
+    }
+
+    fn best_number(&mut self) -> error::Result<BlockNumber> {
+        let header: Value = self.call("chain_getHeader", vec![])?;
+        parse_number(&header["number"])
+    }
+
+    fn block_hash(&mut self, number: BlockNumber) -> error::Result<H256> {
+        let hash: Option<H256> = self.call("chain_getBlockHash", vec![json!(number)])?;
+        hash.ok_or_else(|| error::Error::Input(format!("Block {} not found", number)))
+    }
+
+    /// Events emitted by extrinsic with given index at given block.
+    fn extrinsic_events(&mut self, block_hash: H256, index: u32) -> error::Result<Vec<Event>> {
+        let records: Vec<EventRecord<Event, Hash>> = self
+            .storage_at(&twox_128(b"System Events"), Some(block_hash))?
+            .unwrap_or_default();
+        Ok(records.into_iter()
+            .filter(|r| r.phase == system::Phase::ApplyExtrinsic(index))
+            .map(|r| r.event)
+            .collect())
+    }
+
+    /// Wait until extrinsic with given hash is included into block after `from`,
+    /// returns block hash and extrinsic index in block.
+    fn wait_inclusion(&mut self, hash: H256, from: BlockNumber) -> error::Result<(H256, u32)> {
+        let mut next = from + 1;
+        loop {
+            let best = self.best_number()?;
+            while next <= best {
+                let block_hash = self.block_hash(next)?;
+                let block: Value = self.call("chain_getBlock", vec![json!(block_hash)])?;
+                let extrinsics: Vec<Bytes> = serde_json::from_value(block["block"]["extrinsics"].clone())
+                    .map_err(|e| error::Error::Input(format!("Bad block {} response: {:?}", next, e)))?;
+                if let Some(index) = extrinsics.iter().position(|xt| H256(blake2_256(xt)) == hash) {
+                    return Ok((block_hash, index as u32));
+                }
+                next += 1;
+            }
+            if best >= from + INCLUSION_TIMEOUT {
+                return Err(error::Error::Input(format!("Extrinsic {:?} not included in {} blocks", hash, INCLUSION_TIMEOUT)));
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+}
+
+/// Sign robonomics call, submit it and wait for inclusion, liabilities created or finalized
+/// by extrinsic are printed.
+fn submit(node: &NodeParams, signer: &SignerParams, call: RobonomicsCall<node_runtime::Runtime>) -> error::Result<()> {
+    let pair = signer.pair()?;
+    let account: AccountId = pair.public();
+    let mut rpc = Rpc::connect(&node.url)?;
+
+    let genesis_hash: H256 = rpc.call("chain_getBlockHash", vec![json!(0)])?;
+    let version: Value = rpc.call("state_getRuntimeVersion", vec![])?;
+    let spec_version = version["specVersion"].as_u64()
+        .ok_or_else(|| error::Error::Input("Bad runtime version response".into()))? as u32;
+    let nonce = rpc.account_nonce(&account)?;
+    let best = rpc.best_number()?;
+
+    let extra: SignedExtra = (
+        system::CheckVersion::new(),
+        system::CheckGenesis::new(),
+        system::CheckEra::from(Era::Immortal),
+        system::CheckNonce::from(nonce),
+        system::CheckWeight::new(),
+        TakeSponsoredFees::from(0),
+        CheckRateLimit::new(),
+        CheckLiabilityParty::new(),
+    );
+    let additional = (spec_version, genesis_hash, genesis_hash, (), (), (), (), ());
+    let call = Call::Robonomics(call);
+    let raw_payload = (call, extra, additional);
+    let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
+        pair.sign(&blake2_256(payload)[..])
+    } else {
+        pair.sign(payload)
+    });
+    let (call, extra, _) = raw_payload;
+
+    let extrinsic = UncheckedExtrinsic::new_signed(call, account.into(), signature.into(), extra);
+    let hash: H256 = rpc.call("author_submitExtrinsic", vec![json!(Bytes(extrinsic.encode()))])?;
+    println!("Extrinsic submitted: {:?}", hash);
+
+    let (block_hash, index) = rpc.wait_inclusion(hash, best)?;
+    println!("Extrinsic included in block {:?}", block_hash);
+
+    let mut liabilities = vec![];
+    for event in rpc.extrinsic_events(block_hash, index)? {
+        match event {
+            Event::system(system::Event::ExtrinsicFailed { .. }) =>
+                return Err(error::Error::Input("Extrinsic dispatch failed".into())),
+            Event::robonomics(RobonomicsEvent::NewLiability(index, _)) |
+            Event::robonomics(RobonomicsEvent::Finalized(index, _)) => liabilities.push(index),
+            _ => (),
+        }
+    }
+    if liabilities.is_empty() {
+        println!("No liability created");
+    }
+    for index in liabilities {
+        let key = [&b"Robonomics LiabilityOf"[..], &index.encode()[..]].concat();
+        if let Some(liability) = rpc.storage_at(&blake2_256(&key), Some(block_hash))? {
+            print_liability(index, &liability);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_liability(index: u64, liability: &Liability<Balance, AccountId>) {
+    println!("Liability {}", index);
+    println!("  model:     {}", bs58::encode(&liability.order.model).into_string());
+    println!("  objective: {}", bs58::encode(&liability.order.objective).into_string());
+    println!("  cost:      {}", liability.order.cost);
+    if let Some(ref custodian) = liability.order.custodian {
+        println!("  custodian: {}", custodian.to_ss58check());
+    }
+    println!("  promisee:  {}", liability.promisee.to_ss58check());
+    println!("  promisor:  {}", liability.promisor.to_ss58check());
+    match liability.result {
+        Some(ref result) => println!("  result:    {}", bs58::encode(result).into_string()),
+        None => println!("  result:    <pending>"),
+    }
+}
+
+pub(crate) fn decode_bs58(value: &str) -> error::Result<Vec<u8>> {
+    bs58::decode(value).into_vec()
+        .map_err(|e| error::Error::Input(format!("Invalid base58 value {}: {:?}", value, e)))
+}
+
+/// Block number of JSON-RPC header, hex string or plain number.
+fn parse_number(value: &Value) -> error::Result<BlockNumber> {
+    let number = match value {
+        Value::String(s) if s.starts_with("0x") => BlockNumber::from_str_radix(&s[2..], 16).ok(),
+        value => value.as_u64(),
+    };
+    number.ok_or_else(|| error::Error::Input(format!("Invalid block number: {}", value)))
+}
+
+fn parse_key_type(key_type: &str) -> error::Result<KeyTypeId> {
+    let bytes = key_type.as_bytes();
+    if bytes.len() != 4 {
+        return Err(error::Error::Input(format!("Key type should be 4 characters: {}", key_type)));
+    }
+    let mut id = [0u8; 4];
+    id.copy_from_slice(bytes);
+    Ok(KeyTypeId(id))
+}
+
+pub(crate) fn decode_account(address: &str) -> error::Result<AccountId> {
+    AccountId::from_ss58check(address)
+        .map_err(|e| error::Error::Input(format!("Invalid address {}: {:?}", address, e)))
+}
@@ -0,0 +1,171 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Node configuration file given by `--config`.
+//!
+//! File options are converted into command line flags, flags given on the command line
+//! take precedence over the file.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use serde::Deserialize;
+use substrate_cli::error;
+
+/// Robonomics node configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Chain spec: `dev`, `local`, `robonomics` or path to JSON file.
+    pub chain: Option<String>,
+    /// Node data directory.
+    pub base_path: Option<PathBuf>,
+    /// Node name shown in telemetry.
+    pub name: Option<String>,
+    /// Start as validator.
+    pub validator: bool,
+    /// Start as light client.
+    pub light: bool,
+    /// Libp2p listening port.
+    pub port: Option<u16>,
+    /// Libp2p bootstrap nodes.
+    pub bootnodes: Vec<String>,
+    /// Libp2p reserved nodes.
+    pub reserved_nodes: Vec<String>,
+    /// HTTP JSON-RPC port.
+    pub rpc_port: Option<u16>,
+    /// WebSocket JSON-RPC port.
+    pub ws_port: Option<u16>,
+    /// Listen JSON-RPC on all interfaces.
+    pub rpc_external: bool,
+    /// Listen WebSocket JSON-RPC on all interfaces.
+    pub ws_external: bool,
+    /// Prometheus metrics port.
+    pub prometheus_port: Option<u16>,
+    /// Listen Prometheus metrics port on all interfaces.
+    pub prometheus_external: bool,
+    /// ROS bridge options.
+    pub ros: RosSection,
+}
+
+/// `[ros]` section of configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RosSection {
+    /// Start ROS bridge on light client.
+    pub enable: bool,
+    /// ROS node name.
+    pub node_name: Option<String>,
+    /// Namespace of ROS topics and services.
+    pub namespace: Option<String>,
+    /// ROS bridge account (SS58 address).
+    pub account: Option<String>,
+    /// Real account of ROS bridge proxy calls (SS58 address).
+    pub proxy_for: Option<String>,
+    /// Start liability engine.
+    pub engine: bool,
+    /// IPFS API of liability engine, `host:port`.
+    pub ipfs_api: Option<String>,
+    /// Directory of liability engine rosbag files.
+    pub bag_dir: Option<PathBuf>,
+}
+
+impl ConfigFile {
+    /// Read configuration file in TOML format.
+    pub fn read(path: &PathBuf) -> error::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content)
+            .map_err(|e| error::Error::Input(format!("Invalid config file {}: {}", path.display(), e)))
+    }
+
+    /// Command line flags of configuration options.
+    fn flags(self) -> Vec<(&'static str, Vec<OsString>)> {
+        let mut flags = Vec::new();
+        let mut value = |flag: &'static str, value: Option<OsString>| if let Some(value) = value {
+            flags.push((flag, vec![value]));
+        };
+        value("--chain", self.chain.map(Into::into));
+        value("--base-path", self.base_path.map(Into::into));
+        value("--name", self.name.map(Into::into));
+        value("--port", self.port.map(|v| v.to_string().into()));
+        value("--rpc-port", self.rpc_port.map(|v| v.to_string().into()));
+        value("--ws-port", self.ws_port.map(|v| v.to_string().into()));
+        value("--prometheus-port", self.prometheus_port.map(|v| v.to_string().into()));
+        value("--ros-node-name", self.ros.node_name.map(Into::into));
+        value("--ros-namespace", self.ros.namespace.map(Into::into));
+        value("--ros-account", self.ros.account.map(Into::into));
+        value("--ros-proxy-for", self.ros.proxy_for.map(Into::into));
+        value("--ros-ipfs-api", self.ros.ipfs_api.map(Into::into));
+        value("--ros-bag-dir", self.ros.bag_dir.map(Into::into));
+
+        let switches = [
+            ("--validator", self.validator),
+            ("--light", self.light),
+            ("--rpc-external", self.rpc_external),
+            ("--ws-external", self.ws_external),
+            ("--prometheus-external", self.prometheus_external),
+            ("--ros", self.ros.enable),
+            ("--ros-engine", self.ros.engine),
+        ];
+        flags.extend(switches.iter().filter(|(_, on)| *on).map(|(flag, _)| (*flag, vec![])));
+
+        let lists = vec![
+            ("--bootnodes", self.bootnodes),
+            ("--reserved-nodes", self.reserved_nodes),
+        ];
+        for (flag, values) in lists {
+            if !values.is_empty() {
+                flags.push((flag, values.into_iter().map(Into::into).collect()));
+            }
+        }
+
+        flags
+    }
+}
+
+/// Append options of configuration file given by `--config` to command line arguments,
+/// options already given on the command line are kept as is. Used for node run only.
+pub fn merge_args<I, T>(args: I) -> error::Result<Vec<OsString>> where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+
+    let path = args.iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| args.iter()
+            .filter_map(|arg| arg.to_str())
+            .find(|arg| arg.starts_with("--config="))
+            .map(|arg| arg["--config=".len()..].into())
+        );
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => return Ok(args),
+    };
+
+    let given = |flag: &str, args: &[OsString]| args.iter().any(|arg| arg.to_str().map_or(false, |a|
+        a == flag || a.starts_with(&format!("{}=", flag))
+    ));
+    for (flag, values) in ConfigFile::read(&path)?.flags() {
+        if !given(flag, &args) {
+            args.push(flag.into());
+            args.extend(values);
+        }
+    }
+
+    Ok(args)
+}
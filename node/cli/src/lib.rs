@@ -23,13 +23,21 @@ use futures::future;
 use futures::sync::oneshot;
 use tokio::prelude::Future;
 use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
-use substrate_cli::{informant, parse_and_prepare, NoCustom, ParseAndPrepare};
+use substrate_cli::{informant, parse_and_prepare, ParseAndPrepare};
 use substrate_service::{AbstractService, Roles as ServiceRoles};
 pub use substrate_cli::{VersionInfo, IntoExit, error};
 
-mod chain_spec;
+pub mod chain_spec;
+mod command;
+mod config;
+mod params;
+mod seal;
+mod snapshot;
 #[macro_use]
-mod service;
+pub mod service;
+
+pub use params::RunParams;
+pub use command::CustomSubcommands;
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()> where
@@ -37,9 +45,15 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
     T: Into<std::ffi::OsString> + Clone,
     E: IntoExit,
 {
-    match parse_and_prepare::<NoCustom, NoCustom, _>(&version, "robonomics-node", args) {
+    let args: Vec<std::ffi::OsString> = args.into_iter().map(Into::into).collect();
+    // Configuration file options are node options, other subcommands take command line as is
+    let args = match parse_and_prepare::<CustomSubcommands, RunParams, _>(&version, "robonomics-node", args.clone()) {
+        ParseAndPrepare::Run(_) => config::merge_args(args)?,
+        _ => args,
+    };
+    match parse_and_prepare::<CustomSubcommands, RunParams, _>(&version, "robonomics-node", args) {
         ParseAndPrepare::Run(cmd) => cmd.run::<(), _, _, _, _>(load_spec, exit,
-        |exit, _cli_args, _custom_args, config| {
+        |exit, _cli_args, run_params, config| {
             info!("{}", version.name);
             info!("  version {}", config.full_version());
             info!("  by {}, 2018, 2019", version.author);
@@ -51,14 +65,21 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
             match config.roles {
                 ServiceRoles::LIGHT => run_until_exit(
                     runtime,
-                    service::new_light(config).map_err(|e| format!("{:?}", e))?,
-                    exit
-                ),
-                _ => run_until_exit(
-                    runtime,
-                    service::new_full(config).map_err(|e| format!("{:?}", e))?,
+                    service::new_light(config, run_params).map_err(|e| format!("{:?}", e))?,
                     exit
                 ),
+                _ => match run_params.seal {
+                    Some(_) => run_until_exit(
+                        runtime,
+                        service::new_dev(config, run_params).map_err(|e| format!("{:?}", e))?,
+                        exit
+                    ),
+                    None => run_until_exit(
+                        runtime,
+                        service::new_full(config, run_params).map_err(|e| format!("{:?}", e))?,
+                        exit
+                    ),
+                },
             }.map_err(|e| format!("{:?}", e))
         }),
         ParseAndPrepare::BuildSpec(cmd) => cmd.run(load_spec),
@@ -69,7 +90,7 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
         ParseAndPrepare::PurgeChain(cmd) => cmd.run(load_spec),
         ParseAndPrepare::RevertChain(cmd) => cmd.run_with_builder::<(), _, _, _, _>(|config|
             Ok(new_full_start!(config).0), load_spec),
-        ParseAndPrepare::CustomCommand(_) => Ok(()),
+        ParseAndPrepare::CustomCommand(cmd) => cmd.run(),
     }
 }
 
@@ -0,0 +1,98 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Robonomics node specific command line parameters.
+
+use std::path::PathBuf;
+use structopt::StructOpt;
+use substrate_cli::impl_augment_clap;
+use primitives::crypto::Ss58Codec;
+use node_runtime::types::AccountId;
+use crate::seal::SealMode;
+
+/// Robonomics node run parameters.
+#[derive(Debug, StructOpt, Clone, Default)]
+pub struct RunParams {
+    /// Node configuration file in TOML format, command line flags override its options.
+    #[structopt(long = "config", value_name = "PATH", parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// ROS bridge account (SS58 address), its `robo` key should be present in keystore.
+    /// First `robo` key of keystore is used when not given.
+    #[structopt(long = "ros-account", value_name = "ADDRESS", parse(try_from_str = "parse_account"))]
+    pub ros_account: Option<AccountId>,
+
+    /// Start ROS bridge on light client, full node starts it when built with `ros` feature.
+    #[structopt(long = "ros")]
+    pub ros: bool,
+
+    /// Submit ROS bridge calls as proxy of given real account (SS58 address).
+    #[structopt(long = "ros-proxy-for", value_name = "ADDRESS", parse(try_from_str = "parse_account"))]
+    pub ros_proxy_for: Option<AccountId>,
+
+    /// ROS node name, `robonomics` by default.
+    #[structopt(long = "ros-node-name", value_name = "NAME")]
+    pub ros_node_name: Option<String>,
+
+    /// Namespace of ROS topics and services, e.g. `/robot1`, root namespace by default.
+    #[structopt(long = "ros-namespace", value_name = "NAMESPACE")]
+    pub ros_namespace: Option<String>,
+
+    /// Start liability engine: download objective rosbag from IPFS and play it when
+    /// liability is started.
+    #[structopt(long = "ros-engine")]
+    pub ros_engine: bool,
+
+    /// IPFS API of liability engine, `localhost:5001` by default.
+    #[structopt(long = "ros-ipfs-api", value_name = "HOST:PORT")]
+    pub ros_ipfs_api: Option<String>,
+
+    /// Directory of liability engine rosbag files, current directory by default.
+    #[structopt(long = "ros-bag-dir", value_name = "PATH", parse(from_os_str))]
+    pub ros_bag_dir: Option<PathBuf>,
+
+    /// Serve Prometheus metrics on given local port.
+    #[structopt(long = "prometheus-port", value_name = "PORT")]
+    pub prometheus_port: Option<u16>,
+
+    /// Listen Prometheus metrics port on all interfaces, local only by default.
+    #[structopt(long = "prometheus-external")]
+    pub prometheus_external: bool,
+
+    /// Replace BABE and GRANDPA by instant (block per transaction) or manual block
+    /// sealing, development chain only.
+    #[structopt(long = "seal", value_name = "instant|manual")]
+    pub seal: Option<SealMode>,
+}
+
+impl_augment_clap!(RunParams);
+
+impl RunParams {
+    /// ROS node name.
+    pub fn ros_node_name(&self) -> &str {
+        self.ros_node_name.as_ref().map(String::as_str).unwrap_or("robonomics")
+    }
+
+    /// Namespace of ROS topics and services, empty for root namespace.
+    pub fn ros_namespace(&self) -> &str {
+        self.ros_namespace.as_ref().map(String::as_str).unwrap_or("")
+    }
+}
+
+pub(crate) fn parse_account(address: &str) -> Result<AccountId, String> {
+    AccountId::from_ss58check(address).map_err(|e| format!("Invalid SS58 address: {:?}", e))
+}
@@ -0,0 +1,214 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Instant and manual block sealing for development chains.
+//!
+//! Sealing engine replaces BABE and GRANDPA: block is built by local authority on
+//! every imported transaction (instant) or on explicit trigger (manual), and it's
+//! finalized immediately. Runtime still gets BABE secondary pre-digest of the first
+//! authority and timestamp synchronized with slot number.
+
+use log::{info, warn};
+use std::{
+    collections::HashMap, str::FromStr, time::Duration,
+    sync::{Arc, atomic::{AtomicU64, Ordering}},
+};
+use futures03::{prelude::*, channel::mpsc, future, stream};
+use codec::Encode;
+use client::blockchain::HeaderBackend;
+use consensus_common::{
+    BlockImport, BlockImportParams, BlockOrigin, ForkChoiceStrategy, Environment, Proposer,
+    import_queue::{Verifier, CacheKeyId},
+};
+use babe_primitives::{BabePreDigest, BABE_ENGINE_ID};
+use inherents::{InherentData, InherentDataProviders, InherentIdentifier, ProvideInherentData};
+use sr_primitives::{
+    Justification,
+    generic::{BlockId, Digest, DigestItem},
+    traits::{Block as BlockT, Header as HeaderT},
+};
+use transaction_pool::txpool::{ChainApi, Pool};
+use node_runtime::types::{Block, Hash};
+
+/// Block sealing mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SealMode {
+    /// Seal block on every imported transaction.
+    Instant,
+    /// Seal block on explicit trigger only.
+    Manual,
+}
+
+impl FromStr for SealMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instant" => Ok(SealMode::Instant),
+            "manual" => Ok(SealMode::Manual),
+            _ => Err(format!("Unknown seal mode: {}, expected instant or manual", s)),
+        }
+    }
+}
+
+/// Synthetic block time: each sealed block gets at least one slot later timestamp,
+/// so blocks could be produced more often than slot duration.
+#[derive(Clone)]
+pub struct SealClock {
+    slot_duration: u64,
+    timestamp: Arc<AtomicU64>,
+}
+
+impl SealClock {
+    pub fn new(slot_duration: u64) -> Self {
+        SealClock { slot_duration, timestamp: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Move clock to the next block, returns slot number of the block.
+    fn advance(&self) -> u64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let last = self.timestamp.load(Ordering::SeqCst);
+        let next = now.max(last + self.slot_duration);
+        self.timestamp.store(next, Ordering::SeqCst);
+        next / self.slot_duration
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp.load(Ordering::SeqCst)
+    }
+}
+
+/// Timestamp inherent data provider driven by `SealClock`.
+pub struct SealTimestampProvider(pub SealClock);
+
+impl ProvideInherentData for SealTimestampProvider {
+    fn inherent_identifier(&self) -> &'static InherentIdentifier {
+        &timestamp::INHERENT_IDENTIFIER
+    }
+
+    fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), inherents::Error> {
+        inherent_data.put_data(timestamp::INHERENT_IDENTIFIER, &self.0.timestamp())
+    }
+
+    fn error_to_string(&self, error: &[u8]) -> Option<String> {
+        Some(String::from_utf8_lossy(error).into())
+    }
+}
+
+/// Import queue verifier: sealed blocks are trusted and finalized as is.
+pub struct SealVerifier;
+
+impl<B: BlockT> Verifier<B> for SealVerifier {
+    fn verify(
+        &mut self,
+        origin: BlockOrigin,
+        header: B::Header,
+        justification: Option<Justification>,
+        body: Option<Vec<B::Extrinsic>>,
+    ) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+        let import = BlockImportParams {
+            origin,
+            header,
+            justification,
+            post_digests: vec![],
+            body,
+            finalized: true,
+            auxiliary: vec![],
+            fork_choice: ForkChoiceStrategy::LongestChain,
+        };
+        Ok((import, None))
+    }
+}
+
+/// Build, import and finalize new block on top of the best one.
+fn seal_block<C, E>(
+    client: &C,
+    env: &mut E,
+    inherent_data_providers: &InherentDataProviders,
+    clock: &SealClock,
+) -> Result<Hash, String> where
+    C: HeaderBackend<Block>,
+    for<'a> &'a C: BlockImport<Block>,
+    E: Environment<Block>,
+{
+    let best_hash = client.info().best_hash;
+    let parent = client.header(BlockId::hash(best_hash))
+        .map_err(|e| format!("{:?}", e))?
+        .ok_or("Best block header not found")?;
+
+    let slot_number = clock.advance();
+    let inherent_data = inherent_data_providers.create_inherent_data()
+        .map_err(|e| format!("{:?}", e))?;
+    let pre_digest = BabePreDigest::Secondary { authority_index: 0, slot_number, weight: 0 };
+    let digest = Digest { logs: vec![DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest.encode())] };
+
+    let mut proposer = env.init(&parent).map_err(|e| format!("{:?}", e))?;
+    let block = futures03::executor::block_on(
+        proposer.propose(inherent_data, digest, Duration::from_secs(1))
+    ).map_err(|e| format!("{:?}", e))?;
+
+    let (header, body) = block.deconstruct();
+    let hash = header.hash();
+    let import = BlockImportParams {
+        origin: BlockOrigin::Own,
+        header,
+        justification: None,
+        post_digests: vec![],
+        body: Some(body),
+        finalized: true,
+        auxiliary: vec![],
+        fork_choice: ForkChoiceStrategy::LongestChain,
+    };
+    let mut block_import = client;
+    block_import.import_block(import, HashMap::new()).map_err(|e| format!("{:?}", e))?;
+
+    Ok(hash)
+}
+
+/// Sealing engine routine, blocks are sealed on manual `commands` and, in instant mode,
+/// on transaction pool imports.
+pub fn start_seal_engine<C, E, P>(
+    mode: SealMode,
+    client: Arc<C>,
+    mut env: E,
+    pool: Arc<Pool<P>>,
+    inherent_data_providers: InherentDataProviders,
+    clock: SealClock,
+    commands: mpsc::UnboundedReceiver<()>,
+) -> impl Future<Output=()> where
+    C: HeaderBackend<Block>,
+    for<'a> &'a C: BlockImport<Block>,
+    E: Environment<Block>,
+    P: ChainApi<Block=Block>,
+{
+    info!("Block sealing mode: {:?}", mode);
+    let triggers = match mode {
+        SealMode::Instant => stream::select(pool.import_notification_stream(), commands).boxed(),
+        SealMode::Manual => commands.boxed(),
+    };
+
+    triggers.for_each(move |_| {
+        match seal_block(&*client, &mut env, &inherent_data_providers, &clock) {
+            Ok(hash) => info!("Sealed block {:?}", hash),
+            Err(e) => warn!("Unable to seal block: {}", e),
+        }
+        future::ready(())
+    })
+}
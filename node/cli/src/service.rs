@@ -19,25 +19,40 @@
 
 #![warn(unused_extern_crates)]
 
-use log::info;
+use log::{info, warn};
 use std::sync::Arc;
+use std::net::SocketAddr;
 use grandpa_primitives::{AuthorityPair as GrandpaPair};
 use babe::{import_queue, Config};
 use babe_primitives::{AuthorityPair as BabePair};
 use im_online::sr25519::{AuthorityPair as ImOnlinePair};
-use client::{self, LongestChain};
+use client::{self, LongestChain, BlockchainEvents};
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
-use futures03::future::{FutureExt, TryFutureExt};
+use futures03::{StreamExt as _, future::{FutureExt, TryFutureExt}};
+use codec::Decode;
 use futures::prelude::*;
 use node_executor::Executor;
-use node_runtime::{GenesisConfig, RuntimeApi, types::Block};
+use node_runtime::{
+    GenesisConfig, RuntimeApi, Event, EventRecord, robonomics::RawEvent,
+    types::{Block, Hash, RobonomicsApi},
+};
+#[cfg(feature = "ros")]
+use node_runtime::types::{AccountId, key_types};
 use substrate_service::{
     AbstractService, ServiceBuilder, config::Configuration, error::{Error as ServiceError},
 };
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use network::construct_simple_protocol;
 use inherents::InherentDataProviders;
-use primitives::Pair;
+use primitives::{Pair, Blake2Hasher, twox_128, storage::StorageKey};
+#[cfg(feature = "ros")]
+use primitives::sr25519;
+use sr_primitives::generic::BlockId;
+use sr_primitives::traits::ProvideRuntimeApi;
+use consensus_common::import_queue::BasicQueue;
+use babe_primitives::BabeApi;
+use crate::params::RunParams;
+use crate::seal::{self, SealMode, SealClock, SealTimestampProvider, SealVerifier};
 
 construct_simple_protocol! {
     /// Robonomics protocol attachment for substrate.
@@ -93,12 +108,63 @@ macro_rules! new_full_start {
     }}
 }
 
+/// Starts ROS robonomics API, substrate API services and liability engine of full node.
+///
+/// Services are kept alive until node exit.
+#[cfg(feature = "ros")]
+macro_rules! start_ros {
+    ($service:expr, $run_params:expr, $system_info:expr) => {{
+        let ros_error = |e| ServiceError::Other(format!("ROS error: {:?}", e));
+        let namespace = $run_params.ros_namespace();
+        ros_rpc::init($run_params.ros_node_name()).map_err(ros_error)?;
+
+        let subs = match crate::service::ros_account_key(&$service.keystore(), $run_params.ros_account)? {
+            Some(key) => {
+                let (api, subs) = ros_robonomics::start_api(
+                    $service.client(),
+                    $service.transaction_pool(),
+                    key,
+                    $run_params.ros_proxy_for,
+                    namespace,
+                ).map_err(ros_error)?;
+                $service.spawn_task(api.unit_error().boxed().compat());
+                subs
+            },
+            None => {
+                log::warn!("No `robo` key in keystore, ROS robonomics API disabled");
+                vec![]
+            },
+        };
+
+        let (srvs, pubs) = ros_rpc::start_rpc(
+            $system_info,
+            $service.network(),
+            $service.client(),
+            $service.transaction_pool(),
+            None,
+            namespace,
+        ).map_err(ros_error)?;
+        $service.spawn_task(pubs.unit_error().boxed().compat());
+
+        let engine = match crate::service::ros_engine_config(&$run_params)? {
+            Some(config) => Some(ros_robonomics::start_liability_engine(config).map_err(ros_error)?),
+            None => None,
+        };
+
+        let on_exit = $service.on_exit().then(move |_| {
+            let _ = subs; let _ = srvs; let _ = engine;
+            Ok(())
+        });
+        $service.spawn_task(on_exit);
+    }}
+}
+
 /// Creates a full service from the configuration.
 ///
 /// We need to use a macro because the test suit doesn't work with an opaque service. It expects
 /// concrete types instead.
 macro_rules! new_full {
-    ($config:expr) => {{
+    ($config:expr, $run_params:expr) => {{
         use futures::sync::mpsc;
         use network::DhtEvent;
 
@@ -128,13 +194,18 @@ macro_rules! new_full {
         // 10 000.
         let (dht_event_tx, dht_event_rx) = mpsc::channel::<DhtEvent>(10000);
 
+        let (rpc_tasks, rpc_tasks_spawner) = node_rpc::subscription_tasks();
+
         let service = builder.with_network_protocol(|_| Ok(crate::service::NodeProtocol::new()))?
             .with_finality_proof_provider(|client, backend|
                 Ok(Arc::new(grandpa::FinalityProofProvider::new(backend, client)) as _)
             )?
             .with_dht_event_tx(dht_event_tx)?
+            .with_rpc_extensions(|client, _pool| node_rpc::create(client, rpc_tasks))?
             .build()?;
 
+        service.spawn_task(rpc_tasks_spawner);
+
         let (block_import, link_half, babe_link) = import_setup.take()
                 .expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
@@ -217,52 +288,255 @@ macro_rules! new_full {
             },
         }
 
+        crate::service::start_metrics(&service, &$run_params, true)?;
+
+        #[cfg(not(feature = "ros"))]
+        let _ = $run_params;
+
         #[cfg(feature = "ros")]
         {
-            let (api, subs) = ros_robonomics::start_api(
-                service.client(),
-                service.transaction_pool(),
-                babe_key
-            );
-            service.spawn_task(api.unit_error().boxed().compat());
-    
-            let system_info = ros_rpc::system::SystemInfo {
+            let system_info = ros_rpc::SystemInfo {
                 chain_name: chain_spec.name().into(),
                 impl_name: impl_name.into(),
                 impl_version: impl_version.into(),
                 properties: chain_spec.properties(),
             };
-
-            let (srvs, pubs)= ros_rpc::traits::start_services(
-                system_info,
-                service.network(),
-                service.client(),
-                service.transaction_pool()
-            );
-            service.spawn_task(pubs.unit_error().boxed().compat());
-
-            let on_exit = service.on_exit().then(move |_| { let _ = subs; let _ = srvs; Ok(()) });
-            service.spawn_task(on_exit);
+            start_ros!(service, $run_params, system_info);
         }
 
         Ok((service, inherent_data_providers))
     }}
 }
 
+/// Serve Prometheus metrics when port is given, liability market metrics are collected
+/// from finalized blocks when `with_chain` is set (i.e. node keeps state).
+fn start_metrics<S, B, E>(
+    service: &S,
+    run_params: &RunParams,
+    with_chain: bool,
+) -> Result<(), ServiceError> where
+    S: AbstractService<Block=Block, Backend=B, CallExecutor=E, RuntimeApi=RuntimeApi>,
+    B: client::backend::Backend<Block, Blake2Hasher>,
+    E: client::CallExecutor<Block, Blake2Hasher> + Clone + Send + Sync,
+{
+    if let Some(port) = run_params.prometheus_port {
+        let ip = if run_params.prometheus_external { [0, 0, 0, 0] } else { [127, 0, 0, 1] };
+        let server = node_metrics::start_server(SocketAddr::from((ip, port)))
+            .map_err(|e| ServiceError::Other(format!("Unable to start Prometheus server: {}", e)))?;
+        service.spawn_task(server);
+
+        if with_chain {
+            let chain = chain_metrics(service.client());
+            service.spawn_task(chain.unit_error().boxed().compat());
+        }
+    }
+    Ok(())
+}
+
+/// Update liability market metrics on each finalized block, so events of fork blocks
+/// aren't counted.
+fn chain_metrics<B, E>(
+    client: Arc<client::Client<B, E, Block, RuntimeApi>>,
+) -> impl futures03::Future<Output=()> where
+    B: client::backend::Backend<Block, Blake2Hasher>,
+    E: client::CallExecutor<Block, Blake2Hasher> + Clone + Send + Sync,
+{
+    let events_key = StorageKey(twox_128(b"System Events").to_vec());
+    client.finality_notification_stream().for_each(move |block| {
+        let id = BlockId::hash(block.hash);
+        let records: Vec<EventRecord<Event, Hash>> = match client.storage(&id, &events_key) {
+            Ok(Some(data)) => Decode::decode(&mut &data.0[..]).unwrap_or_default(),
+            Ok(None) => vec![],
+            Err(e) => {
+                warn!("Unable to read events of block {:?}: {:?}", block.hash, e);
+                vec![]
+            },
+        };
+
+        for record in records {
+            match record.event {
+                Event::robonomics(RawEvent::NewLiability(..)) => node_metrics::LIABILITIES_CREATED.inc(),
+                Event::robonomics(RawEvent::Finalized(..)) => node_metrics::LIABILITIES_FINALIZED.inc(),
+                _ => (),
+            }
+        }
+
+        // Orders could be matched or dropped without dedicated event, depth is read from storage
+        match client.runtime_api().queue_depth(&id) {
+            Ok((demands, offers)) => {
+                node_metrics::DEMAND_QUEUE_DEPTH.set(demands as i64);
+                node_metrics::OFFER_QUEUE_DEPTH.set(offers as i64);
+            },
+            Err(e) => warn!("Unable to read queue depth at {:?}: {:?}", block.hash, e),
+        }
+
+        futures03::future::ready(())
+    })
+}
+
+/// ROS bridge key from keystore: `robo` key of given account or the first one found.
+#[cfg(feature = "ros")]
+fn ros_account_key(
+    keystore: &keystore::KeyStorePtr,
+    account: Option<AccountId>,
+) -> Result<Option<sr25519::Pair>, ServiceError> {
+    let store = keystore.read();
+    let public = match account {
+        Some(account) => account,
+        None => match store.public_keys_by_type::<sr25519::Public>(key_types::ROBO)?.into_iter().next() {
+            Some(public) => public,
+            None => return Ok(None),
+        },
+    };
+    let key = store.key_pair_by_type::<sr25519::Pair>(&public, key_types::ROBO)?;
+    Ok(Some(key))
+}
+
+/// Liability engine options when engine is enabled.
+#[cfg(feature = "ros")]
+fn ros_engine_config(run_params: &RunParams) -> Result<Option<ros_robonomics::EngineConfig>, ServiceError> {
+    if !run_params.ros_engine {
+        return Ok(None);
+    }
+    let mut config = ros_robonomics::EngineConfig {
+        namespace: run_params.ros_namespace().into(),
+        .. Default::default()
+    };
+    if let Some(ref address) = run_params.ros_ipfs_api {
+        let mut parts = address.rsplitn(2, ':');
+        let port = parts.next().and_then(|port| port.parse().ok());
+        match (parts.next(), port) {
+            (Some(host), Some(port)) => {
+                config.ipfs_host = host.into();
+                config.ipfs_port = port;
+            },
+            _ => return Err(ServiceError::Other(format!("Invalid IPFS API address: {}", address))),
+        }
+    }
+    if let Some(ref bag_dir) = run_params.ros_bag_dir {
+        config.bag_dir = bag_dir.clone();
+    }
+    Ok(Some(config))
+}
+
 /// Builds a new service for a full client.
 pub fn new_full<C: Send + Default + 'static>(
-    config: Configuration<C, GenesisConfig>
+    config: Configuration<C, GenesisConfig>,
+    run_params: RunParams,
 ) -> Result<impl AbstractService, ServiceError> {
-    new_full!(config).map(|(service, _)| service)
+    new_full!(config, run_params).map(|(service, _)| service)
+}
+
+/// Builds a new service for a development chain with instant or manual block sealing.
+pub fn new_dev<C: Send + Default + 'static>(
+    config: Configuration<C, GenesisConfig>,
+    run_params: RunParams,
+) -> Result<impl AbstractService, ServiceError> {
+    use futures03::channel::mpsc;
+
+    let mode: SealMode = run_params.seal
+        .ok_or_else(|| ServiceError::Other("Block sealing mode isn't given".into()))?;
+
+    if config.chain_spec.id() != "dev" {
+        return Err(ServiceError::Other("Block sealing is supported by development chain only".into()));
+    }
+
+    #[cfg(feature = "ros")]
+    let system_info = ros_rpc::SystemInfo {
+        chain_name: config.chain_spec.name().into(),
+        impl_name: config.impl_name.into(),
+        impl_version: config.impl_version.into(),
+        properties: config.chain_spec.properties(),
+    };
+
+    let inherent_data_providers = InherentDataProviders::new();
+    let (seal_tx, seal_rx) = mpsc::unbounded();
+    let rpc_seal_tx = seal_tx.clone();
+    let (rpc_tasks, rpc_tasks_spawner) = node_rpc::subscription_tasks();
+
+    let service = ServiceBuilder::new_full::<Block, RuntimeApi, Executor>(config)?
+        .with_select_chain(|_config, backend| {
+            Ok(LongestChain::new(backend.clone()))
+        })?
+        .with_transaction_pool(|config, client|
+            Ok(TransactionPool::new(config, transaction_pool::ChainApi::new(client)))
+        )?
+        .with_import_queue(|_config, client, _select_chain, _transaction_pool| {
+            Ok(BasicQueue::new(SealVerifier, Box::new(client), None, None))
+        })?
+        .with_network_protocol(|_| Ok(NodeProtocol::new()))?
+        .with_rpc_extensions(|client, _pool| {
+            let mut io = node_rpc::create(client, rpc_tasks);
+            io.add_method("dev_sealBlock", move |_| {
+                rpc_seal_tx.unbounded_send(())
+                    .map(|_| jsonrpc_core::Value::Null)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())
+            });
+            io
+        })?
+        .build()?;
+
+    service.spawn_task(rpc_tasks_spawner);
+
+    // Finality tracker inherent is required by runtime
+    grandpa::setup_disabled_grandpa(
+        service.client(),
+        &inherent_data_providers,
+        service.network(),
+    )?;
+
+    let client = service.client();
+    let slot_duration = client.runtime_api().startup_data(&BlockId::number(0))?.slot_duration;
+    let clock = SealClock::new(slot_duration);
+    inherent_data_providers.register_provider(SealTimestampProvider(clock.clone()))
+        .map_err(|e| ServiceError::Other(format!("{:?}", e)))?;
+
+    let proposer = basic_authorship::ProposerFactory {
+        client: service.client(),
+        transaction_pool: service.transaction_pool(),
+    };
+
+    let engine = seal::start_seal_engine(
+        mode,
+        client,
+        proposer,
+        service.transaction_pool(),
+        inherent_data_providers,
+        clock,
+        seal_rx,
+    );
+    service.spawn_essential_task(engine.unit_error().boxed().compat());
+
+    start_metrics(&service, &run_params, true)?;
+
+    #[cfg(not(feature = "ros"))]
+    let _ = seal_tx;
+
+    #[cfg(feature = "ros")]
+    {
+        start_ros!(service, run_params, system_info);
+
+        let srvs = ros_rpc::dev::start_dev_services(seal_tx, run_params.ros_namespace())
+            .map_err(|e| ServiceError::Other(format!("ROS error: {:?}", e)))?;
+        let on_exit = service.on_exit().then(move |_| { let _ = srvs; Ok(()) });
+        service.spawn_task(on_exit);
+    }
+
+    Ok(service)
 }
 
 /// Builds a new service for a light client.
 pub fn new_light<C: Send + Default + 'static>(
-    config: Configuration<C, GenesisConfig>
+    config: Configuration<C, GenesisConfig>,
+    run_params: RunParams,
 ) -> Result<impl AbstractService, ServiceError> {
 
     let inherent_data_providers = InherentDataProviders::new();
     let mut tasks_to_spawn = Vec::new();
+    let mut remote_fetcher = None;
+    #[cfg(feature = "ros")]
+    let (impl_name, impl_version, chain_spec) =
+        (config.impl_name.clone(), config.impl_version.clone(), config.chain_spec.clone());
 
     let service = ServiceBuilder::new_light::<Block, RuntimeApi, Executor>(config)?
         .with_select_chain(|_config, backend| {
@@ -272,7 +546,8 @@ pub fn new_light<C: Send + Default + 'static>(
             Ok(TransactionPool::new(config, transaction_pool::ChainApi::new(client)))
         )?
         .with_import_queue_and_fprb(|_config, client, backend, fetcher, _select_chain, transaction_pool| {
-            let fetch_checker = fetcher 
+            remote_fetcher = fetcher.clone();
+            let fetch_checker = fetcher
                 .map(|fetcher| fetcher.checker().clone())
                 .ok_or_else(|| "Trying to start light import queue without active fetch checker")?;
             let block_import = grandpa::light_block_import::<_, _, _, RuntimeApi, _>(
@@ -307,5 +582,69 @@ pub fn new_light<C: Send + Default + 'static>(
     // spawn any futures that were created in the previous setup steps
     tasks_to_spawn.into_iter().for_each(|t| service.spawn_task(t));
 
+    // Light client keeps no state, block events aren't available for chain metrics
+    start_metrics(&service, &run_params, false)?;
+
+    #[cfg(not(feature = "ros"))]
+    let _ = (run_params, remote_fetcher);
+
+    #[cfg(feature = "ros")]
+    {
+        if run_params.ros {
+            let ros_error = |e| ServiceError::Other(format!("ROS error: {:?}", e));
+            let fetcher = remote_fetcher
+                .ok_or_else(|| ServiceError::Other("Light client fetcher isn't available".into()))?;
+            let namespace = run_params.ros_namespace();
+            ros_rpc::init(run_params.ros_node_name()).map_err(ros_error)?;
+
+            let subs = match ros_account_key(&service.keystore(), run_params.ros_account)? {
+                Some(key) => {
+                    let (api, subs) = ros_robonomics::start_light_api(
+                        service.client(),
+                        service.transaction_pool(),
+                        key,
+                        run_params.ros_proxy_for,
+                        namespace,
+                        fetcher.clone(),
+                    ).map_err(ros_error)?;
+                    service.spawn_task(api.unit_error().boxed().compat());
+                    subs
+                },
+                None => {
+                    log::warn!("No `robo` key in keystore, ROS robonomics API disabled");
+                    vec![]
+                },
+            };
+
+            let system_info = ros_rpc::SystemInfo {
+                chain_name: chain_spec.name().into(),
+                impl_name: impl_name.into(),
+                impl_version: impl_version.into(),
+                properties: chain_spec.properties(),
+            };
+
+            let (srvs, pubs) = ros_rpc::start_rpc(
+                system_info,
+                service.network(),
+                service.client(),
+                service.transaction_pool(),
+                Some(fetcher as _),
+                namespace,
+            ).map_err(ros_error)?;
+            service.spawn_task(pubs.unit_error().boxed().compat());
+
+            let engine = match ros_engine_config(&run_params)? {
+                Some(config) => Some(ros_robonomics::start_liability_engine(config).map_err(ros_error)?),
+                None => None,
+            };
+
+            let on_exit = service.on_exit().then(move |_| {
+                let _ = subs; let _ = srvs; let _ = engine;
+                Ok(())
+            });
+            service.spawn_task(on_exit);
+        }
+    }
+
     Ok(service)
 }
@@ -0,0 +1,242 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Robonomics state snapshot: liabilities and open orders in human-readable form,
+//! addresses are SS58 and binary fields are base58 encoded.
+
+use std::io::Write;
+use serde::{Serialize, Deserialize};
+use substrate_cli::error;
+use primitives::{H256, crypto::Ss58Codec};
+use node_runtime::{
+    RobonomicsConfig,
+    robonomics::{self, Capacity},
+    types::{AccountId, Balance, BlockNumber, Hash},
+};
+use crate::command::{decode_bs58, decode_account};
+
+type Liability = robonomics::Liability<Balance, AccountId>;
+type Demand = robonomics::Demand<Balance, AccountId>;
+type Offer = robonomics::Offer<Balance, AccountId, BlockNumber>;
+
+/// Order parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRecord {
+    pub model: String,
+    pub objective: String,
+    pub cost: Balance,
+    pub custodian: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiabilityRecord {
+    pub index: u64,
+    #[serde(flatten)]
+    pub order: OrderRecord,
+    pub promisee: String,
+    pub promisor: String,
+    pub result: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemandRecord {
+    pub order_hash: H256,
+    #[serde(flatten)]
+    pub order: OrderRecord,
+    pub sender: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfferRecord {
+    pub order_hash: H256,
+    #[serde(flatten)]
+    pub order: OrderRecord,
+    pub sender: String,
+    /// Remaining number of matches, zero means unlimited.
+    pub capacity: u32,
+    pub expires: Option<BlockNumber>,
+}
+
+/// Robonomics state at given block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub block: BlockNumber,
+    pub block_hash: H256,
+    pub liability_count: u64,
+    pub liabilities: Vec<LiabilityRecord>,
+    pub demands: Vec<DemandRecord>,
+    pub offers: Vec<OfferRecord>,
+}
+
+impl Snapshot {
+    /// Convert runtime snapshot into records.
+    pub fn new(
+        block: BlockNumber,
+        block_hash: H256,
+        snapshot: robonomics::Snapshot<Balance, AccountId, BlockNumber, Hash>,
+    ) -> Self {
+        let liabilities = snapshot.liabilities.into_iter()
+            .map(|(index, liability)| LiabilityRecord {
+                index,
+                order: order_record(&liability.order),
+                promisee: liability.promisee.to_ss58check(),
+                promisor: liability.promisor.to_ss58check(),
+                result: liability.result.map(|r| bs58::encode(r).into_string()),
+            })
+            .collect();
+        let demands = snapshot.demands.into_iter()
+            .flat_map(|(order_hash, demands)| demands.into_iter().map(move |demand| DemandRecord {
+                order_hash,
+                order: order_record(&demand.order),
+                sender: demand.sender.to_ss58check(),
+            }))
+            .collect();
+        let offers = snapshot.offers.into_iter()
+            .flat_map(|(order_hash, offers)| offers.into_iter().map(move |offer| OfferRecord {
+                order_hash,
+                order: order_record(&offer.order),
+                sender: offer.sender.to_ss58check(),
+                capacity: match offer.capacity {
+                    Capacity::Limited(n) => n,
+                    Capacity::Unlimited => 0,
+                },
+                expires: offer.expires,
+            }))
+            .collect();
+        Snapshot { block, block_hash, liability_count: snapshot.liability_count, liabilities, demands, offers }
+    }
+
+    /// Write snapshot as pretty JSON.
+    pub fn write_json<W: Write>(&self, out: W) -> error::Result<()> {
+        serde_json::to_writer_pretty(out, self)
+            .map_err(|e| error::Error::Input(format!("Unable to write snapshot: {:?}", e)))
+    }
+
+    /// Write snapshot as CSV table, one row per liability, demand or offer.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> error::Result<()> {
+        writeln!(out, "kind,index,order_hash,model,objective,cost,custodian,sender,promisee,promisor,result,capacity,expires")?;
+        for l in &self.liabilities {
+            writeln!(out, "liability,{},,{},,{},{},{},,",
+                l.index, csv_order(&l.order), l.promisee, l.promisor, opt(&l.result))?;
+        }
+        for d in &self.demands {
+            writeln!(out, "demand,,{:?},{},{},,,,,", d.order_hash, csv_order(&d.order), d.sender)?;
+        }
+        for o in &self.offers {
+            writeln!(out, "offer,,{:?},{},{},,,,{},{}",
+                o.order_hash, csv_order(&o.order), o.sender, o.capacity, opt(&o.expires))?;
+        }
+        Ok(())
+    }
+
+    /// Robonomics genesis of a new chain seeded by snapshot.
+    ///
+    /// Block numbers restart from genesis, so offer expiration is shifted by snapshot block
+    /// and offers already expired at snapshot block are dropped.
+    pub fn genesis_config(&self) -> error::Result<RobonomicsConfig> {
+        let mut liabilities = Vec::with_capacity(self.liabilities.len());
+        for l in &self.liabilities {
+            let result = match l.result {
+                Some(ref result) => Some(decode_bs58(result)?),
+                None => None,
+            };
+            liabilities.push((l.index, Liability {
+                order: decode_order(&l.order)?,
+                promisee: decode_account(&l.promisee)?,
+                promisor: decode_account(&l.promisor)?,
+                result,
+            }));
+        }
+
+        let mut demands = Vec::with_capacity(self.demands.len());
+        for d in &self.demands {
+            demands.push(Demand { order: decode_order(&d.order)?, sender: decode_account(&d.sender)? });
+        }
+
+        let mut offers = Vec::with_capacity(self.offers.len());
+        for o in &self.offers {
+            if o.expires.map_or(false, |expires| self.block > expires) {
+                continue;
+            }
+            offers.push(Offer {
+                order: decode_order(&o.order)?,
+                sender: decode_account(&o.sender)?,
+                capacity: match o.capacity {
+                    0 => Capacity::Unlimited,
+                    n => Capacity::Limited(n),
+                },
+                expires: o.expires.map(|expires| expires - self.block),
+            });
+        }
+
+        Ok(RobonomicsConfig { liabilities, demands, offers })
+    }
+}
+
+/// Ensure promisees of not finalized liabilities have enough genesis balance
+/// to reserve liability costs.
+pub fn check_reserves(config: &RobonomicsConfig, balances: &[(AccountId, Balance)]) -> error::Result<()> {
+    let mut reserves: Vec<(AccountId, Balance)> = Vec::new();
+    for (_, liability) in config.liabilities.iter().filter(|(_, l)| l.result.is_none()) {
+        match reserves.iter_mut().find(|(account, _)| *account == liability.promisee) {
+            Some((_, reserve)) => *reserve = reserve.saturating_add(liability.order.cost),
+            None => reserves.push((liability.promisee.clone(), liability.order.cost)),
+        }
+    }
+    for (account, reserve) in reserves {
+        let balance = balances.iter()
+            .filter(|(a, _)| *a == account)
+            .fold(0, |sum: Balance, (_, b)| sum.saturating_add(*b));
+        if balance < reserve {
+            return Err(error::Error::Input(format!(
+                "Genesis balance {} of {} is too low to reserve {} of liability costs",
+                balance, account.to_ss58check(), reserve,
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn order_record(order: &robonomics::Order<Balance, AccountId>) -> OrderRecord {
+    OrderRecord {
+        model: bs58::encode(&order.model).into_string(),
+        objective: bs58::encode(&order.objective).into_string(),
+        cost: order.cost,
+        custodian: order.custodian.as_ref().map(Ss58Codec::to_ss58check),
+    }
+}
+
+fn decode_order(order: &OrderRecord) -> error::Result<robonomics::Order<Balance, AccountId>> {
+    let custodian = match order.custodian {
+        Some(ref address) => Some(decode_account(address)?),
+        None => None,
+    };
+    Ok(robonomics::Order {
+        model: decode_bs58(&order.model)?,
+        objective: decode_bs58(&order.objective)?,
+        cost: order.cost,
+        custodian,
+    })
+}
+
+fn csv_order(order: &OrderRecord) -> String {
+    format!("{},{},{},{}", order.model, order.objective, order.cost, opt(&order.custodian))
+}
+
+fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
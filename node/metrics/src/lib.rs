@@ -0,0 +1,113 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Prometheus metrics of Robonomics liability market and ROS bridge.
+//!
+//! Metrics are registered in default registry and served as text by local HTTP server,
+//! see `start_server`.
+
+use std::net::SocketAddr;
+use log::{info, error};
+use futures::Future;
+use hyper::{Body, Request, Response, Server, service::service_fn_ok, header::CONTENT_TYPE};
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, TextEncoder, IntCounter, IntGauge, Histogram,
+    register_int_counter, register_int_gauge, register_histogram,
+};
+
+lazy_static! {
+    /// Liabilities created in finalized blocks.
+    pub static ref LIABILITIES_CREATED: IntCounter = register_int_counter!(
+        "robonomics_liabilities_created_total", "Liabilities created"
+    ).unwrap();
+
+    /// Liabilities finalized in finalized blocks.
+    pub static ref LIABILITIES_FINALIZED: IntCounter = register_int_counter!(
+        "robonomics_liabilities_finalized_total", "Liabilities finalized"
+    ).unwrap();
+
+    /// Demands waiting for matching offer, read from chain storage on finalized blocks.
+    pub static ref DEMAND_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "robonomics_demand_queue_depth", "Demands waiting for matching offer"
+    ).unwrap();
+
+    /// Offers waiting for matching demand, read from chain storage on finalized blocks.
+    pub static ref OFFER_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "robonomics_offer_queue_depth", "Offers waiting for matching demand"
+    ).unwrap();
+
+    /// Extrinsics submitted into transaction pool by ROS bridge.
+    pub static ref EXTRINSICS_SUBMITTED: IntCounter = register_int_counter!(
+        "robonomics_ros_extrinsics_submitted_total", "Extrinsics submitted by ROS bridge"
+    ).unwrap();
+
+    /// ROS bridge extrinsics that could not be built.
+    pub static ref EXTRINSICS_FAILED: IntCounter = register_int_counter!(
+        "robonomics_ros_extrinsics_failed_total", "Extrinsics failed to build by ROS bridge"
+    ).unwrap();
+
+    /// ROS bridge extrinsics rejected by transaction pool.
+    pub static ref EXTRINSICS_REJECTED: IntCounter = register_int_counter!(
+        "robonomics_ros_extrinsics_rejected_total", "Extrinsics rejected by transaction pool"
+    ).unwrap();
+
+    /// Messages that could not be published into ROS topics.
+    pub static ref ROS_PUBLISH_ERRORS: IntCounter = register_int_counter!(
+        "robonomics_ros_publish_errors_total", "ROS publish errors"
+    ).unwrap();
+
+    /// Rosbag playback duration of liability objective.
+    pub static ref ROSBAG_PLAYBACK_DURATION: Histogram = register_histogram!(
+        "robonomics_rosbag_playback_duration_seconds", "Rosbag playback duration",
+        vec![1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0]
+    ).unwrap();
+}
+
+/// Metrics of default registry in Prometheus text format.
+fn encode_metrics() -> Response<Body> {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => Response::builder()
+            .header(CONTENT_TYPE, encoder.format_type())
+            .body(Body::from(buffer))
+            .unwrap(),
+        Err(e) => {
+            error!("Unable to encode metrics: {}", e);
+            Response::builder()
+                .status(500)
+                .body(Body::empty())
+                .unwrap()
+        },
+    }
+}
+
+/// Start HTTP server that responds with metrics on `/metrics` path.
+pub fn start_server(addr: SocketAddr) -> Result<impl Future<Item=(), Error=()>, hyper::Error> {
+    let server = Server::try_bind(&addr)?
+        .serve(|| service_fn_ok(|req: Request<Body>| match req.uri().path() {
+            "/metrics" => encode_metrics(),
+            _ => Response::builder()
+                .status(404)
+                .body(Body::empty())
+                .unwrap(),
+        }));
+    info!("Prometheus metrics served on http://{}/metrics", addr);
+
+    Ok(server.map_err(|e| error!("Prometheus server error: {}", e)))
+}
@@ -0,0 +1,68 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Robonomics node specific RPC extensions.
+
+use std::sync::Arc;
+use futures::{Future, Stream, sync::mpsc, future::{Executor, ExecuteError}};
+use client::{blockchain::HeaderBackend, BlockchainEvents};
+use sr_primitives::traits::ProvideRuntimeApi;
+use substrate_rpc::{Metadata, Subscriptions};
+use node_runtime::types::{Block, IdentityApi, RobonomicsApi};
+
+pub mod robonomics;
+
+/// Subscription notification task.
+pub type Task = Box<dyn Future<Item=(), Error=()> + Send>;
+
+/// Executor of subscription tasks: tasks are queued until service spawns them,
+/// see `subscription_tasks`.
+#[derive(Clone)]
+pub struct TaskSender(mpsc::UnboundedSender<Task>);
+
+impl Executor<Task> for TaskSender {
+    fn execute(&self, task: Task) -> Result<(), ExecuteError<Task>> {
+        self.0.unbounded_send(task).map_err(|e| ExecuteError::new(
+            futures::future::ExecuteErrorKind::Shutdown,
+            e.into_inner(),
+        ))
+    }
+}
+
+/// Create subscription tasks executor and future that spawns queued tasks,
+/// it should be spawned on service runtime.
+pub fn subscription_tasks() -> (TaskSender, impl Future<Item=(), Error=()>) {
+    let (tx, rx) = mpsc::unbounded();
+    let spawner = rx.for_each(|task| {
+        tokio::spawn(task);
+        Ok(())
+    });
+    (TaskSender(tx), spawner)
+}
+
+/// Instantiate all RPC extensions of robonomics node.
+pub fn create<C>(client: Arc<C>, tasks: TaskSender) -> jsonrpc_core::IoHandler<Metadata> where
+    C: ProvideRuntimeApi + HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+    C::Api: RobonomicsApi<Block> + IdentityApi<Block>,
+{
+    use robonomics::{Robonomics, RobonomicsRpc};
+
+    let subscriptions = Subscriptions::new(Arc::new(tasks));
+    let mut io = jsonrpc_core::IoHandler::default();
+    io.extend_with(RobonomicsRpc::to_delegate(Robonomics::new(client, subscriptions)));
+    io
+}
@@ -0,0 +1,348 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Robonomics liability market RPC: liabilities, open orders and event subscriptions.
+//!
+//! Payloads follow fields of ROS bridge messages: model, objective and result
+//! are base58 encoded, accounts are SS58 addresses.
+
+use std::sync::Arc;
+use log::warn;
+use serde::Serialize;
+use codec::Decode;
+use futures::{Future, Sink, Stream};
+use futures03::{StreamExt, TryStreamExt};
+use jsonrpc_core::{Result, Error, ErrorCode};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use client::{blockchain::HeaderBackend, BlockchainEvents};
+use sr_primitives::{generic::BlockId, traits::ProvideRuntimeApi};
+use primitives::{twox_128, crypto::Ss58Codec, storage::{StorageKey, StorageData}};
+use substrate_rpc::{Metadata, Subscriptions};
+use node_runtime::{
+    Event, EventRecord,
+    robonomics::{self, RawEvent, Capacity},
+    types::{Block, Hash, AccountId, Balance, BlockNumber, IdentityApi, RobonomicsApi},
+};
+
+/// Order of demand, offer or liability.
+#[derive(Debug, Clone, Serialize)]
+pub struct Order {
+    pub model: String,
+    pub objective: String,
+    pub cost: String,
+}
+
+/// Demand waiting for matching offer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Demand {
+    pub order: Order,
+    pub sender: String,
+    /// Verified display name of sender, empty when unknown
+    pub sender_name: String,
+}
+
+/// Offer waiting for matching demand.
+#[derive(Debug, Clone, Serialize)]
+pub struct Offer {
+    pub order: Order,
+    pub sender: String,
+    /// Remaining number of matches, zero means unlimited
+    pub capacity: u32,
+    /// Offer expiration block number, zero means never
+    pub expires: u64,
+    /// Verified display name of sender, empty when unknown
+    pub sender_name: String,
+}
+
+/// Liability created by matched demand and offer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Liability {
+    pub id: u64,
+    pub order: Order,
+    pub promisee: String,
+    pub promisor: String,
+    /// Liability result, empty when not finalized
+    pub result: String,
+    /// Verified display names of parties, empty when unknown
+    pub promisee_name: String,
+    pub promisor_name: String,
+}
+
+/// Demands and offers of model waiting for matching.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OpenOrders {
+    pub demands: Vec<Demand>,
+    pub offers: Vec<Offer>,
+}
+
+/// Robonomics RPC methods.
+#[rpc]
+pub trait RobonomicsRpc {
+    /// RPC metadata
+    type Metadata;
+
+    /// Get liability by index.
+    #[rpc(name = "robonomics_liability")]
+    fn liability(&self, id: u64) -> Result<Option<Liability>>;
+
+    /// Get liabilities where account (SS58 address) is promisee or promisor.
+    #[rpc(name = "robonomics_liabilitiesOf")]
+    fn liabilities_of(&self, account: String) -> Result<Vec<Liability>>;
+
+    /// Get demands and offers of model (base58 encoded) waiting for matching.
+    #[rpc(name = "robonomics_openOrders")]
+    fn open_orders(&self, model: String) -> Result<OpenOrders>;
+
+    /// New liabilities subscription.
+    #[pubsub(subscription = "robonomics_liabilities", subscribe, name = "robonomics_subscribeLiabilities")]
+    fn subscribe_liabilities(&self, metadata: Self::Metadata, subscriber: Subscriber<Liability>);
+
+    /// Unsubscribe from new liabilities.
+    #[pubsub(subscription = "robonomics_liabilities", unsubscribe, name = "robonomics_unsubscribeLiabilities")]
+    fn unsubscribe_liabilities(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+
+    /// New demands subscription.
+    #[pubsub(subscription = "robonomics_demands", subscribe, name = "robonomics_subscribeDemands")]
+    fn subscribe_demands(&self, metadata: Self::Metadata, subscriber: Subscriber<Demand>);
+
+    /// Unsubscribe from new demands.
+    #[pubsub(subscription = "robonomics_demands", unsubscribe, name = "robonomics_unsubscribeDemands")]
+    fn unsubscribe_demands(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+
+    /// New offers subscription.
+    #[pubsub(subscription = "robonomics_offers", subscribe, name = "robonomics_subscribeOffers")]
+    fn subscribe_offers(&self, metadata: Self::Metadata, subscriber: Subscriber<Offer>);
+
+    /// Unsubscribe from new offers.
+    #[pubsub(subscription = "robonomics_offers", unsubscribe, name = "robonomics_unsubscribeOffers")]
+    fn unsubscribe_offers(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+}
+
+/// Robonomics RPC implementation.
+pub struct Robonomics<C> {
+    client: Arc<C>,
+    subscriptions: Subscriptions,
+}
+
+impl<C> Robonomics<C> where
+    C: ProvideRuntimeApi + HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+    C::Api: RobonomicsApi<Block> + IdentityApi<Block>,
+{
+    /// Create new instance of Robonomics RPC.
+    pub fn new(client: Arc<C>, subscriptions: Subscriptions) -> Self {
+        Robonomics { client, subscriptions }
+    }
+
+    fn best_block(&self) -> Hash {
+        self.client.info().best_hash
+    }
+
+    /// Subscribe for robonomics events converted into notification payloads.
+    fn subscribe<T, F>(&self, subscriber: Subscriber<T>, convert: F) where
+        T: Serialize + Send + 'static,
+        F: Fn(&C, Hash, robonomics::Event<node_runtime::Runtime>) -> Option<T> + Send + 'static,
+    {
+        let events_key = StorageKey(twox_128(b"System Events").to_vec());
+        let stream = match self.client.storage_changes_notification_stream(Some(&[events_key]), None) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = subscriber.reject(client_error(e));
+                return;
+            }
+        };
+
+        let client = self.client.clone();
+        let notifications = stream
+            .map(move |(block, changes)| {
+                let records: Vec<EventRecord<Event, Hash>> = changes.iter()
+                    .filter_map(|(_, _, data)| data.as_ref())
+                    .filter_map(|StorageData(data)| Vec::<EventRecord<Event, Hash>>::decode(&mut &data[..]).ok())
+                    .flatten()
+                    .collect();
+                records.into_iter()
+                    .filter_map(|record| match record.event {
+                        Event::robonomics(e) => convert(&*client, block, e),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map(|items| futures03::stream::iter(items))
+            .flatten()
+            .map(|item| Ok::<_, ()>(Ok::<_, Error>(item)))
+            .compat();
+
+        self.subscriptions.add(subscriber, |sink| {
+            sink.sink_map_err(|e| warn!("Error sending robonomics notification: {:?}", e))
+                .send_all(notifications)
+                .map(|_| ())
+        });
+    }
+}
+
+impl<C> RobonomicsRpc for Robonomics<C> where
+    C: ProvideRuntimeApi + HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+    C::Api: RobonomicsApi<Block> + IdentityApi<Block>,
+{
+    type Metadata = Metadata;
+
+    fn liability(&self, id: u64) -> Result<Option<Liability>> {
+        let block = self.best_block();
+        let liability = self.client.runtime_api().liability(&BlockId::hash(block), id)
+            .map_err(client_error)?;
+        Ok(liability.map(|l| liability_info(&*self.client, block, id, l)))
+    }
+
+    fn liabilities_of(&self, account: String) -> Result<Vec<Liability>> {
+        let account = AccountId::from_ss58check(&account)
+            .map_err(|e| invalid_params(format!("Invalid SS58 address: {:?}", e)))?;
+        let block = self.best_block();
+        let liabilities = self.client.runtime_api().liabilities_of(&BlockId::hash(block), account)
+            .map_err(client_error)?;
+        Ok(liabilities.into_iter()
+            .map(|(id, l)| liability_info(&*self.client, block, id, l))
+            .collect())
+    }
+
+    fn open_orders(&self, model: String) -> Result<OpenOrders> {
+        let model = bs58::decode(&model).into_vec()
+            .map_err(|e| invalid_params(format!("Invalid base58 model: {:?}", e)))?;
+        let block = self.best_block();
+        let (demands, offers) = self.client.runtime_api().open_orders(&BlockId::hash(block), model)
+            .map_err(client_error)?;
+        Ok(OpenOrders {
+            demands: demands.into_iter().map(|d| demand_info(&*self.client, block, d)).collect(),
+            offers: offers.into_iter().map(|o| offer_info(&*self.client, block, o)).collect(),
+        })
+    }
+
+    fn subscribe_liabilities(&self, _metadata: Self::Metadata, subscriber: Subscriber<Liability>) {
+        self.subscribe(subscriber, |client, block, event| match event {
+            RawEvent::NewLiability(id, liability) => Some(liability_info(client, block, id, liability)),
+            _ => None,
+        })
+    }
+
+    fn unsubscribe_liabilities(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        Ok(self.subscriptions.cancel(id))
+    }
+
+    fn subscribe_demands(&self, _metadata: Self::Metadata, subscriber: Subscriber<Demand>) {
+        self.subscribe(subscriber, |client, block, event| match event {
+            RawEvent::NewDemand(_, demand) => Some(demand_info(client, block, demand)),
+            _ => None,
+        })
+    }
+
+    fn unsubscribe_demands(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        Ok(self.subscriptions.cancel(id))
+    }
+
+    fn subscribe_offers(&self, _metadata: Self::Metadata, subscriber: Subscriber<Offer>) {
+        self.subscribe(subscriber, |client, block, event| match event {
+            RawEvent::NewOffer(_, offer) => Some(offer_info(client, block, offer)),
+            _ => None,
+        })
+    }
+
+    fn unsubscribe_offers(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        Ok(self.subscriptions.cancel(id))
+    }
+}
+
+fn client_error<E: std::fmt::Debug>(e: E) -> Error {
+    Error {
+        code: ErrorCode::ServerError(1),
+        message: "Runtime API error".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+fn invalid_params(message: String) -> Error {
+    Error {
+        code: ErrorCode::InvalidParams,
+        message,
+        data: None,
+    }
+}
+
+/// Verified display name of account at given block, empty string when unknown.
+fn display_name<C>(client: &C, block: Hash, account: &AccountId) -> String where
+    C: ProvideRuntimeApi,
+    C::Api: IdentityApi<Block>,
+{
+    client.runtime_api().display_name(&BlockId::hash(block), account.clone())
+        .ok()
+        .and_then(|name| name)
+        .map(|name| String::from_utf8_lossy(&name).into_owned())
+        .unwrap_or_default()
+}
+
+fn order_info(order: &robonomics::Order<Balance, AccountId>) -> Order {
+    Order {
+        model: bs58::encode(&order.model).into_string(),
+        objective: bs58::encode(&order.objective).into_string(),
+        cost: order.cost.to_string(),
+    }
+}
+
+fn demand_info<C>(client: &C, block: Hash, demand: robonomics::Demand<Balance, AccountId>) -> Demand where
+    C: ProvideRuntimeApi,
+    C::Api: IdentityApi<Block>,
+{
+    Demand {
+        order: order_info(&demand.order),
+        sender: demand.sender.to_ss58check(),
+        sender_name: display_name(client, block, &demand.sender),
+    }
+}
+
+fn offer_info<C>(client: &C, block: Hash, offer: robonomics::Offer<Balance, AccountId, BlockNumber>) -> Offer where
+    C: ProvideRuntimeApi,
+    C::Api: IdentityApi<Block>,
+{
+    Offer {
+        order: order_info(&offer.order),
+        sender: offer.sender.to_ss58check(),
+        capacity: match offer.capacity {
+            Capacity::Limited(n) => n,
+            Capacity::Unlimited => 0,
+        },
+        expires: offer.expires.unwrap_or(0),
+        sender_name: display_name(client, block, &offer.sender),
+    }
+}
+
+fn liability_info<C>(
+    client: &C,
+    block: Hash,
+    id: u64,
+    liability: robonomics::Liability<Balance, AccountId>,
+) -> Liability where
+    C: ProvideRuntimeApi,
+    C::Api: IdentityApi<Block>,
+{
+    Liability {
+        id,
+        order: order_info(&liability.order),
+        promisee: liability.promisee.to_ss58check(),
+        promisor: liability.promisor.to_ss58check(),
+        result: liability.result.map(|r| bs58::encode(r).into_string()).unwrap_or_default(),
+        promisee_name: display_name(client, block, &liability.promisee),
+        promisor_name: display_name(client, block, &liability.promisor),
+    }
+}
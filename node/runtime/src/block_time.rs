@@ -0,0 +1,85 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Block time parameters set in chain spec: slot duration and epoch duration.
+//! Timestamp and BABE modules get them through `Get` implementations below,
+//! time periods of other modules are converted into blocks by `InBlocks`.
+
+use rstd::marker::PhantomData;
+use support::{decl_module, decl_storage, StorageValue, traits::Get};
+use sr_primitives::traits::SaturatedConversion;
+use crate::constants::time::{SLOT_DURATION, EPOCH_DURATION_IN_SLOTS};
+use crate::types::Moment;
+
+pub trait Trait: system::Trait {}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as BlockTime {
+        /// Slot duration in milliseconds.
+        pub SlotDuration get(slot_duration) config(): Moment = SLOT_DURATION;
+
+        /// Epoch duration in slots.
+        pub EpochDuration get(epoch_duration) config(): u64 = EPOCH_DURATION_IN_SLOTS;
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Count of blocks produced on average during given period in milliseconds, at least one.
+    pub fn blocks(period: Moment) -> T::BlockNumber {
+        (period / Self::slot_duration().max(1)).max(1).saturated_into()
+    }
+}
+
+/// Timestamp minimum period, half of slot duration.
+pub struct MinimumPeriod<T>(PhantomData<T>);
+
+impl<T: Trait> Get<Moment> for MinimumPeriod<T> {
+    fn get() -> Moment {
+        <Module<T>>::slot_duration() / 2
+    }
+}
+
+/// BABE epoch duration in slots.
+pub struct SlotsPerEpoch<T>(PhantomData<T>);
+
+impl<T: Trait> Get<u64> for SlotsPerEpoch<T> {
+    fn get() -> u64 {
+        <Module<T>>::epoch_duration()
+    }
+}
+
+/// Expected block time, secondary slots make it equal to slot duration.
+pub struct ExpectedBlockTime<T>(PhantomData<T>);
+
+impl<T: Trait> Get<Moment> for ExpectedBlockTime<T> {
+    fn get() -> Moment {
+        <Module<T>>::slot_duration()
+    }
+}
+
+/// Time period `P` in milliseconds converted into blocks.
+pub struct InBlocks<T, P>(PhantomData<(T, P)>);
+
+impl<T: Trait, P: Get<Moment>> Get<T::BlockNumber> for InBlocks<T, P> {
+    fn get() -> T::BlockNumber {
+        <Module<T>>::blocks(P::get())
+    }
+}
@@ -30,29 +30,21 @@ pub mod currency {
 pub mod time {
     use crate::types::Moment;
 
-    /// Since BABE is probabilistic this is the average expected block time that
-    /// we are targetting. Blocks will be produced at a minimum duration defined
-    /// by `SLOT_DURATION`, but some slots will not be allocated to any
-    /// authority and hence no block will be produced. We expect to have this
-    /// block time on average following the defined slot duration and the value
-    /// of `c` configured for BABE.
-    /// This value is only used indirectly to define the unit constants below
-    /// that are expressed in blocks. The rest of the code should use
-    /// `SLOT_DURATION` instead (like the timestamp module for calculating the
-    /// minimum period).
+    /// Default slot duration, could be changed in chain spec by `block_time` config.
+    ///
+    /// Since BABE is probabilistic the average expected block time follows the
+    /// slot duration and the value of `c` configured for BABE; with secondary
+    /// slots enabled each slot gets a block, so slot duration is used as
+    /// expected block time.
     /// <https://research.web3.foundation/en/latest/polkadot/BABE/Babe/#6-practical-results>
-    pub const MILLISECS_PER_BLOCK: Moment = 6000;
-    pub const SECS_PER_BLOCK: Moment = MILLISECS_PER_BLOCK / 1000;
-
     pub const SLOT_DURATION: Moment = 6000;
 
-    pub const EPOCH_DURATION_IN_BLOCKS: Moment = 10 * MINUTES;
-    pub const EPOCH_DURATION_IN_SLOTS: Moment = {
-        const SLOT_FILL_RATE: f64 = MILLISECS_PER_BLOCK as f64 / SLOT_DURATION as f64;
-        (EPOCH_DURATION_IN_BLOCKS as f64 * SLOT_FILL_RATE) as Moment
-    };
+    /// Default epoch duration in slots, 10 minutes with default slot duration.
+    pub const EPOCH_DURATION_IN_SLOTS: u64 = 100;
 
-    pub const MINUTES: Moment = 60 / SECS_PER_BLOCK;
+    /// Time periods in milliseconds, converted into blocks by `block_time::InBlocks`
+    /// according to configured slot duration.
+    pub const MINUTES: Moment = 60_000;
     pub const HOURS: Moment = MINUTES * 60;
     pub const DAYS: Moment = HOURS * 24;
 }
@@ -61,10 +53,8 @@ pub mod time {
 // _ratio_ of it yielding the portion which is accessible to normal transactions (reserving the rest
 // for operational ones). `TARGET_BLOCK_FULLNESS` is entirely independent and the system module is
 // not aware of if, nor should it care about it. This constant simply denotes on which ratio of the
-// _maximum_ block weight we tweak the fees. It does NOT care about the type of the dispatch.
-//
-// For the system to be configured in a sane way, `TARGET_BLOCK_FULLNESS` should always be less than
-// the ratio that `system` module uses to find normal transaction quota.
+// _normal_ block weight (`AvailableBlockRatio` of maximum) we tweak the fees. Operational dispatches
+// exceeding the normal quota are ignored by fee update.
 /// Fee-related.
 pub mod fee {
     pub use sr_primitives::Perbill;
@@ -72,3 +62,13 @@ pub mod fee {
     /// The block saturation level. Fees will be updates based on this value.
     pub const TARGET_BLOCK_FULLNESS: Perbill = Perbill::from_percent(25);
 }
+
+/// Custom transaction validity error codes, reported as `InvalidTransaction::Custom`.
+pub mod validity {
+    /// Sponsor allowance of the robot is exhausted and robot can't pay transaction fee itself.
+    pub const SPONSOR_ALLOWANCE_EXHAUSTED: u8 = 1;
+    /// Account sent too many robonomics calls during rate limit window.
+    pub const RATE_LIMIT_EXCEEDED: u8 = 2;
+    /// Operational robonomics call sent by account that isn't liability party.
+    pub const NOT_LIABILITY_PARTY: u8 = 3;
+}
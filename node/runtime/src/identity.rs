@@ -0,0 +1,249 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Account identity registry. Operators attach display name, URL and contact email
+//! to their accounts, registrars appointed by governance verify attached data.
+
+use rstd::prelude::*;
+#[cfg(feature = "std")]
+use serde_derive::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use system::ensure_signed;
+use support::{
+    StorageMap, decl_module, decl_storage, decl_event, ensure,
+    traits::{ReservableCurrency, Currency, Get, EnsureOrigin},
+    dispatch::Result,
+};
+use sr_primitives::traits::Hash;
+
+/// Identity data attached to account.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default)]
+pub struct IdentityInfo {
+    /// Human readable name.
+    pub display: Vec<u8>,
+    /// Web site.
+    pub url: Vec<u8>,
+    /// Contact email.
+    pub email: Vec<u8>,
+}
+
+/// Registered identity.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct Registration<Balance> {
+    pub info: IdentityInfo,
+    /// Reserved deposit, returned when identity cleared.
+    pub deposit: Balance,
+    /// Set by registrar, dropped on identity change.
+    pub verified: bool,
+}
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+    /// Deposit currency.
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// Amount reserved while identity is registered.
+    type IdentityDeposit: Get<BalanceOf<Self>>;
+    /// Maximal length of identity field in bytes.
+    type MaxFieldLength: Get<u32>;
+    /// Origin allowed to appoint and dismiss registrars.
+    type RegistrarOrigin: EnsureOrigin<Self::Origin>;
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin
+    {
+        fn deposit_event() = default;
+
+        /// Attach identity to sender account, verification is dropped.
+        pub fn set_identity(origin, info: IdentityInfo) -> Result {
+            let who = ensure_signed(origin)?;
+            let max = T::MaxFieldLength::get() as usize;
+            ensure!(info.display.len() <= max, "display name too long");
+            ensure!(info.url.len() <= max, "url too long");
+            ensure!(info.email.len() <= max, "email too long");
+
+            let deposit = match Self::identity_of(&who) {
+                Some(registration) => registration.deposit,
+                None => {
+                    let deposit = T::IdentityDeposit::get();
+                    T::Currency::reserve(&who, deposit)
+                        .map_err(|_| "not enough balance for identity deposit")?;
+                    deposit
+                },
+            };
+
+            <IdentityOf<T>>::insert(&who, Registration { info, deposit, verified: false });
+            Self::deposit_event(RawEvent::IdentitySet(who));
+
+            Ok(())
+        }
+
+        /// Remove identity of sender account and return deposit.
+        pub fn clear_identity(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            let registration = <IdentityOf<T>>::take(&who).ok_or("identity not found")?;
+
+            T::Currency::unreserve(&who, registration.deposit);
+            Self::deposit_event(RawEvent::IdentityCleared(who, registration.deposit));
+
+            Ok(())
+        }
+
+        /// Appoint account allowed to verify identities.
+        pub fn add_registrar(origin, account: T::AccountId) -> Result {
+            T::RegistrarOrigin::ensure_origin(origin)?;
+            ensure!(!Self::registrars().contains(&account), "already registrar");
+
+            <Registrars<T>>::mutate(|registrars| registrars.push(account.clone()));
+            Self::deposit_event(RawEvent::RegistrarAdded(account));
+
+            Ok(())
+        }
+
+        /// Dismiss registrar, identities verified before keep their status.
+        pub fn remove_registrar(origin, account: T::AccountId) -> Result {
+            T::RegistrarOrigin::ensure_origin(origin)?;
+            ensure!(Self::registrars().contains(&account), "not a registrar");
+
+            <Registrars<T>>::mutate(|registrars| registrars.retain(|r| r != &account));
+            Self::deposit_event(RawEvent::RegistrarRemoved(account));
+
+            Ok(())
+        }
+
+        /// Verify identity of account, `info_hash` should be a hash of current identity data.
+        pub fn verify(origin, who: T::AccountId, info_hash: T::Hash) -> Result {
+            let registrar = ensure_signed(origin)?;
+            ensure!(Self::registrars().contains(&registrar), "not a registrar");
+            let mut registration = Self::identity_of(&who).ok_or("identity not found")?;
+            ensure!(T::Hashing::hash_of(&registration.info) == info_hash, "identity changed");
+
+            registration.verified = true;
+            <IdentityOf<T>>::insert(&who, registration);
+            Self::deposit_event(RawEvent::IdentityVerified(who));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Identity {
+        /// Get identity of account.
+        pub IdentityOf get(identity_of): map T::AccountId => Option<Registration<BalanceOf<T>>>;
+        /// Accounts allowed to verify identities.
+        pub Registrars get(registrars): Vec<T::AccountId>;
+    }
+}
+
+decl_event! {
+    pub enum Event<T>
+        where <T as system::Trait>::AccountId,
+              Balance = BalanceOf<T>
+    {
+        /// Identity attached to account.
+        IdentitySet(AccountId),
+
+        /// Identity removed, deposit returned.
+        IdentityCleared(AccountId, Balance),
+
+        /// Identity verified by registrar.
+        IdentityVerified(AccountId),
+
+        /// Registrar appointed.
+        RegistrarAdded(AccountId),
+
+        /// Registrar dismissed.
+        RegistrarRemoved(AccountId),
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Display name of account with verified identity.
+    pub fn display_name(who: &T::AccountId) -> Option<Vec<u8>> {
+        Self::identity_of(who)
+            .filter(|registration| registration.verified)
+            .map(|registration| registration.info.display)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{assert_ok, assert_noop};
+    use crate::mock::*;
+
+    fn info(display: &[u8]) -> IdentityInfo {
+        IdentityInfo { display: display.to_vec(), url: vec![], email: vec![] }
+    }
+
+    fn info_hash(display: &[u8]) -> <Test as system::Trait>::Hash {
+        <Test as system::Trait>::Hashing::hash_of(&info(display))
+    }
+
+    fn root() -> Origin {
+        system::RawOrigin::Root.into()
+    }
+
+    #[test]
+    fn registrar_verifies_identity() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Identity::add_registrar(root(), 3));
+            assert_ok!(Identity::set_identity(Origin::signed(1), info(b"robot")));
+            assert_eq!(Balances::reserved_balance(&1), 10);
+            assert_eq!(Identity::display_name(&1), None);
+
+            assert_noop!(Identity::verify(Origin::signed(2), 1, info_hash(b"robot")), "not a registrar");
+            assert_noop!(Identity::verify(Origin::signed(3), 1, info_hash(b"other")), "identity changed");
+            assert_ok!(Identity::verify(Origin::signed(3), 1, info_hash(b"robot")));
+            assert_eq!(Identity::display_name(&1), Some(b"robot".to_vec()));
+
+            // Identity change drops verification
+            assert_ok!(Identity::set_identity(Origin::signed(1), info(b"robot 2")));
+            assert_eq!(Identity::display_name(&1), None);
+            assert_eq!(Balances::reserved_balance(&1), 10);
+        });
+    }
+
+    #[test]
+    fn registrars_are_appointed_by_governance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert!(Identity::add_registrar(Origin::signed(1), 3).is_err());
+            assert_ok!(Identity::add_registrar(root(), 3));
+            assert_noop!(Identity::add_registrar(root(), 3), "already registrar");
+            assert_ok!(Identity::remove_registrar(root(), 3));
+            assert!(Identity::registrars().is_empty());
+        });
+    }
+
+    #[test]
+    fn clear_identity_returns_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(Identity::set_identity(Origin::signed(1), info(&[0; 17])), "display name too long");
+            assert_ok!(Identity::set_identity(Origin::signed(1), info(b"robot")));
+            assert_ok!(Identity::clear_identity(Origin::signed(1)));
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert_eq!(Identity::identity_of(&1), None);
+        });
+    }
+}
@@ -17,13 +17,22 @@
 ///////////////////////////////////////////////////////////////////////////////
 
 use sr_primitives::{
-    weights::{Weight, WeightMultiplier},
+    weights::{Weight, WeightMultiplier, DispatchInfo},
     traits::{Convert, Saturating},
     Fixed64
 };
+use primitives::u32_trait::{_1, _2};
+use support::traits::{Get, EnsureOrigin, Time};
 use crate::constants::fee::TARGET_BLOCK_FULLNESS;
-use crate::{Balances, MaximumBlockWeight};
-use crate::types::Balance;
+use crate::sponsorship::IsSponsoredCall;
+use crate::robonomics::UnwrapCall;
+use crate::{
+    Balances, Treasury, System, Proxy, Multisig, MaximumBlockWeight, AvailableBlockRatio,
+    TransactionBaseFee, TransactionByteFee,
+    Origin, Call, CouncilCollective, ProxyCall,
+};
+use crate::multisig::Call as MultisigCall;
+use crate::types::{AccountId, Balance, Moment};
 
 /// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
 /// calculation.
@@ -52,14 +61,17 @@ impl Convert<u128, Balance> for CurrencyToVoteHandler {
 ///   next_weight = weight * (1 + (v . diff) + (v . diff)^2 / 2)
 ///
 /// https://research.web3.foundation/en/latest/polkadot/Token%20Economics/#relay-chain-transaction-fees
+///
+/// Saturation is measured against normal dispatch capacity, weight of operational dispatches
+/// (e.g. liability finalization) above it doesn't raise fees of normal transactions.
 pub struct WeightMultiplierUpdateHandler;
 
 impl Convert<(Weight, WeightMultiplier), WeightMultiplier> for WeightMultiplierUpdateHandler {
 	fn convert(previous_state: (Weight, WeightMultiplier)) -> WeightMultiplier {
 		let (block_weight, multiplier) = previous_state;
-        let max_weight = MaximumBlockWeight::get();
+        let max_weight = AvailableBlockRatio::get() * MaximumBlockWeight::get();
         let target_weight = (TARGET_BLOCK_FULLNESS * max_weight) as u128;
-		let block_weight = block_weight as u128;
+		let block_weight = block_weight.min(max_weight) as u128;
 
 		// determines if the first_term is positive
 		let positive = block_weight >= target_weight;
@@ -118,3 +130,107 @@ impl Convert<Weight, Balance> for WeightToFee {
         Balance::from(x).saturating_mul(1_000)
     }
 }
+
+/// Treasury account, used as protocol fee destination.
+pub struct TreasuryAccount;
+
+impl Get<AccountId> for TreasuryAccount {
+    fn get() -> AccountId {
+        Treasury::account_id()
+    }
+}
+
+/// Staking era clock, it never advances so staking era payout is always empty while
+/// era points are still recorded. Validators are paid by rewards module.
+///
+/// Staking module of this substrate version has no other way to disable its payout,
+/// so its `Reward` and `OnRewardMinted` handlers are never called and its `CurrentEraStart`
+/// is always zero; era start time should not be read from staking storage.
+pub struct StakingEraClock;
+
+impl Time for StakingEraClock {
+    type Moment = Moment;
+
+    fn now() -> Moment {
+        0
+    }
+}
+
+/// Origin check passed by root (i.e. referendum) or at least half of the council.
+pub struct EnsureRootOrHalfCouncil;
+
+impl EnsureOrigin<Origin> for EnsureRootOrHalfCouncil {
+    type Success = ();
+
+    fn try_origin(o: Origin) -> Result<Self::Success, Origin> {
+        <system::EnsureRoot<AccountId>>::try_origin(o).or_else(|o|
+            <collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>>::try_origin(o)
+        )
+    }
+}
+
+/// Computes the final transaction fee the same way as `balances::TakeFees` does.
+///
+/// The final fee is composed of:
+///   - _length-fee_: paid for size of the transaction (normal dispatches only);
+///   - _weight-fee_: `WeightToFee` of the transaction weight adjusted by current multiplier;
+///   - _tip_: optional, added on top.
+pub struct TransactionFee;
+
+impl Convert<(usize, DispatchInfo, Balance), Balance> for TransactionFee {
+    fn convert(params: (usize, DispatchInfo, Balance)) -> Balance {
+        let (len, info, tip) = params;
+        let len_fee = if info.pay_length_fee() {
+            let per_byte = TransactionByteFee::get();
+            TransactionBaseFee::get().saturating_add(per_byte.saturating_mul(len as Balance))
+        } else {
+            0
+        };
+
+        let weight_fee = {
+            // cap the weight to the maximum defined in runtime, otherwise it will be the `Bounded`
+            // maximum of its data type, which is not desired.
+            let capped_weight = info.weight.min(MaximumBlockWeight::get());
+            let weight_update = System::next_weight_multiplier();
+            WeightToFee::convert(weight_update.apply_to(capped_weight))
+        };
+
+        len_fee.saturating_add(weight_fee).saturating_add(tip)
+    }
+}
+
+/// Robonomics calls, sent directly or wrapped by proxy or multisig, could be paid by robot sponsor.
+pub struct RobonomicsCalls;
+
+impl IsSponsoredCall<Call> for RobonomicsCalls {
+    fn is_sponsored(call: &Call) -> bool {
+        match call {
+            Call::Robonomics(_) => true,
+            Call::Proxy(ProxyCall::proxy(_, call)) => Self::is_sponsored(call),
+            Call::Multisig(MultisigCall::as_multi(_, _, call)) => Self::is_sponsored(call),
+            _ => false,
+        }
+    }
+}
+
+/// Proxy and multisig calls, wrapped call is checked against real or multisig account.
+pub struct WrappedCalls;
+
+impl UnwrapCall<AccountId, Call> for WrappedCalls {
+    fn unwrap_call<'a>(who: &AccountId, call: &'a Call) -> Option<(AccountId, &'a Call)> {
+        match call {
+            // Call of account that isn't proxy is checked against sender itself
+            Call::Proxy(ProxyCall::proxy(real, call)) => {
+                let real = if Proxy::is_proxy(real, who) { real.clone() } else { who.clone() };
+                Some((real, &**call))
+            },
+            Call::Multisig(MultisigCall::as_multi(threshold, other_signatories, call)) => {
+                let real = Multisig::signatories(who.clone(), other_signatories.clone())
+                    .map(|signatories| Multisig::multi_account_id(&signatories, *threshold))
+                    .unwrap_or_else(|_| who.clone());
+                Some((real, &**call))
+            },
+            _ => None,
+        }
+    }
+}
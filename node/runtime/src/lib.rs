@@ -22,21 +22,30 @@
 #![recursion_limit="256"]
 
 pub mod robonomics;
+pub mod sponsorship;
+pub mod rewards;
+pub mod block_time;
+pub mod proxy;
+pub mod multisig;
+pub mod identity;
 pub mod constants;
 pub mod types;
 pub mod impls;
+#[cfg(test)]
+mod mock;
 
 use rstd::prelude::*;
 use support::{construct_runtime, parameter_types};
 use primitives::OpaqueMetadata;
+use primitives::u32_trait::{_1, _2, _3, _5};
 use sr_primitives::{
     ApplyResult, generic, create_runtime_str, key_types
 };
-use sr_primitives::weights::Weight;
+use sr_primitives::weights::{Weight, GetDispatchInfo};
 use sr_primitives::transaction_validity::TransactionValidity;
 use sr_primitives::traits::{
     self, BlakeTwo256, Block as BlockT,
-    DigestFor, NumberFor, StaticLookup, SaturatedConversion,
+    DigestFor, NumberFor, StaticLookup, SaturatedConversion, Convert,
 };
 use babe::{AuthorityId as BabeId};
 use authority_discovery_primitives::{AuthorityId as EncodedAuthorityId, Signature as EncodedSignature};
@@ -48,7 +57,10 @@ use client::{
     block_builder::api::{CheckInherentsResult, InherentData, self as block_builder_api},
     runtime_api, impl_runtime_apis
 };
-use crate::impls::{CurrencyToVoteHandler, WeightMultiplierUpdateHandler, WeightToFee};
+use crate::impls::{
+    CurrencyToVoteHandler, WeightMultiplierUpdateHandler, WeightToFee, TransactionFee,
+    TreasuryAccount, EnsureRootOrHalfCouncil, RobonomicsCalls, WrappedCalls, StakingEraClock,
+};
 use crate::constants::{time::*, currency::*};
 use crate::types::{
     Balance, BlockNumber, Index, Hash, AccountId, AccountIndex, Moment, Signature,
@@ -63,6 +75,7 @@ use version::NativeVersion;
 pub use balances::Call as BalancesCall;
 pub use timestamp::Call as TimestampCall;
 pub use robonomics::Call as RobonomicsCall;
+pub use proxy::Call as ProxyCall;
 pub use sr_primitives::{Permill, Perbill, impl_opaque_keys};
 pub use support::StorageValue;
 pub use staking::StakerStatus;
@@ -77,7 +90,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("robonomics"),
     impl_name: create_runtime_str!("robonomics-airalab"),
     authoring_version: 1,
-    spec_version: 30,
+    spec_version: 55,
     impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
 };
@@ -132,36 +145,32 @@ impl system::Trait for Runtime {
     type AvailableBlockRatio = AvailableBlockRatio;
 }
 
-parameter_types! {
-    pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
-}
+/// Time period given in milliseconds converted into blocks using configured block time.
+pub type InBlocks<P> = block_time::InBlocks<Runtime, P>;
+
+impl block_time::Trait for Runtime {}
 
 impl timestamp::Trait for Runtime {
     /// A timestamp: seconds since the unix epoch.
     type Moment = Moment;
     type OnTimestampSet = Babe;
-    type MinimumPeriod = MinimumPeriod;
+    type MinimumPeriod = block_time::MinimumPeriod<Runtime>;
 }
 
 parameter_types! {
     pub const UncleGenerations: u64 = 5;
 }
 
-parameter_types! {
-    pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
-    pub const ExpectedBlockTime: Moment = MILLISECS_PER_BLOCK;
-}
-
 impl babe::Trait for Runtime {
-    type EpochDuration = EpochDuration;
-    type ExpectedBlockTime = ExpectedBlockTime;
+    type EpochDuration = block_time::SlotsPerEpoch<Runtime>;
+    type ExpectedBlockTime = block_time::ExpectedBlockTime<Runtime>;
 }
 
 impl authorship::Trait for Runtime {
     type FindAuthor = session::FindAccountFromAuthorIndex<Self, Babe>;
     type UncleGenerations = UncleGenerations;
     type FilterUncle = ();
-    type EventHandler = Staking;
+    type EventHandler = Rewards;
 }
 
 impl indices::Trait for Runtime {
@@ -222,7 +231,7 @@ impl_opaque_keys! {
 // in `SessionKeys`.
 
 impl session::Trait for Runtime {
-    type OnSessionEnding = Staking;
+    type OnSessionEnding = Rewards;
     type SessionHandler = SessionHandlers;
     type ShouldEndSession = Babe;
     type Event = Event;
@@ -244,17 +253,25 @@ parameter_types! {
 
 impl staking::Trait for Runtime {
     type Currency = Balances;
-    type Time = Timestamp;
+    /// Era clock never advances, so staking doesn't mint era payout.
+    type Time = StakingEraClock;
     type CurrencyToVote = CurrencyToVoteHandler;
     type OnRewardMinted = ();
     type Event = Event;
-    type Slash = ();
+    /// Slashed funds are sent to treasury.
+    type Slash = Treasury;
+    /// Validators are paid by rewards module.
     type Reward = ();
     type SessionsPerEra = SessionsPerEra;
     type BondingDuration = BondingDuration;
     type SessionInterface = Self;
 }
 
+impl rewards::Trait for Runtime {
+    type Event = Event;
+    type Time = Timestamp;
+}
+
 impl grandpa::Trait for Runtime {
     type Event = Event;
 }
@@ -270,7 +287,72 @@ impl finality_tracker::Trait for Runtime {
     type ReportLatency = ReportLatency;
 }
 
-impl sudo::Trait for Runtime {
+parameter_types! {
+    pub const ProposalBond: Permill = Permill::from_percent(5);
+    pub const ProposalBondMinimum: Balance = 1 * XRT;
+    pub const SpendPeriod: Moment = 1 * DAYS;
+    pub const Burn: Permill = Permill::from_percent(0);
+}
+
+impl treasury::Trait for Runtime {
+    type Currency = Balances;
+    type ApproveOrigin = collective::EnsureProportionAtLeast<_3, _5, AccountId, CouncilCollective>;
+    type RejectOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type Event = Event;
+    type MintedForSpending = ();
+    type ProposalRejection = ();
+    type ProposalBond = ProposalBond;
+    type ProposalBondMinimum = ProposalBondMinimum;
+    type SpendPeriod = InBlocks<SpendPeriod>;
+    type Burn = Burn;
+}
+
+parameter_types! {
+    pub const LaunchPeriod: Moment = 28 * DAYS;
+    pub const VotingPeriod: Moment = 28 * DAYS;
+    pub const EmergencyVotingPeriod: Moment = 3 * DAYS;
+    pub const MinimumDeposit: Balance = 100 * XRT;
+    pub const EnactmentPeriod: Moment = 30 * DAYS;
+    pub const CooloffPeriod: Moment = 28 * DAYS;
+}
+
+impl democracy::Trait for Runtime {
+    type Proposal = Call;
+    type Event = Event;
+    type Currency = Balances;
+    type EnactmentPeriod = InBlocks<EnactmentPeriod>;
+    type LaunchPeriod = InBlocks<LaunchPeriod>;
+    type VotingPeriod = InBlocks<VotingPeriod>;
+    type EmergencyVotingPeriod = InBlocks<EmergencyVotingPeriod>;
+    type MinimumDeposit = MinimumDeposit;
+    /// A straight majority of the council can decide what their next motion is.
+    type ExternalOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+    /// A majority can have the next scheduled referendum be a straight majority-carries vote.
+    type ExternalMajorityOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+    /// A unanimous council can have the next scheduled referendum be a straight default-carries
+    /// (NTB) vote.
+    type ExternalDefaultOrigin = collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+    /// Two thirds of the technical committee can have an external referendum be tabled
+    /// immediately and with a shorter voting period.
+    type FastTrackOrigin = collective::EnsureProportionAtLeast<_2, _3, AccountId, TechnicalCollective>;
+    /// To cancel a proposal which has been passed, 2/3 of the council must agree to it.
+    type CancellationOrigin = collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+    /// Any single technical committee member may veto a coming council proposal, however they can
+    /// only do it once and it lasts only for the cooloff period.
+    type VetoOrigin = collective::EnsureMember<AccountId, TechnicalCollective>;
+    type CooloffPeriod = InBlocks<CooloffPeriod>;
+}
+
+type CouncilCollective = collective::Instance1;
+impl collective::Trait<CouncilCollective> for Runtime {
+    type Origin = Origin;
+    type Proposal = Call;
+    type Event = Event;
+}
+
+type TechnicalCollective = collective::Instance2;
+impl collective::Trait<TechnicalCollective> for Runtime {
+    type Origin = Origin;
     type Proposal = Call;
     type Event = Event;
 }
@@ -294,11 +376,111 @@ impl offences::Trait for Runtime {
 
 impl authority_discovery::Trait for Runtime {}
 
+parameter_types! {
+    pub const ProtocolFee: Permill = Permill::from_percent(1);
+    pub const RateLimitCalls: u32 = 10;
+    pub const RateLimitWindow: Moment = 1 * MINUTES;
+    pub const MaxOfferMatches: u32 = 16;
+}
+
 impl robonomics::Trait for Runtime {
     /// Native token as processing currency.
     type Currency = Balances;
     /// The uniquitous event type.
     type Event = Event;
+    /// Default protocol fee, could be changed by governance.
+    type ProtocolFee = ProtocolFee;
+    /// Protocol fees are collected by treasury.
+    type Treasury = TreasuryAccount;
+    /// Robonomics parameters are governed by referendum or council majority.
+    type GovernanceOrigin = EnsureRootOrHalfCouncil;
+    /// Default demand and offer rate limit, could be changed by governance.
+    type RateLimitCalls = RateLimitCalls;
+    type RateLimitWindow = InBlocks<RateLimitWindow>;
+    /// Offer creates at most this count of liabilities in single call.
+    type MaxOfferMatches = MaxOfferMatches;
+    /// Proxy and multisig calls are checked as calls of real account.
+    type WrappedCalls = WrappedCalls;
+}
+
+impl sponsorship::Trait for Runtime {
+    type Currency = Balances;
+    type ComputeFee = TransactionFee;
+    type TransactionPayment = ();
+    /// Operators could pay fees of robonomics calls only.
+    type SponsoredCalls = RobonomicsCalls;
+    type Event = Event;
+}
+
+impl proxy::Trait for Runtime {
+    type Call = Call;
+    type Event = Event;
+}
+
+parameter_types! {
+    pub const MultisigApprovalDeposit: Balance = 10 * GLUSHKOV;
+}
+
+impl multisig::Trait for Runtime {
+    type Call = Call;
+    type Currency = Balances;
+    type ApprovalDeposit = MultisigApprovalDeposit;
+    type Event = Event;
+}
+
+parameter_types! {
+    pub const IdentityDeposit: Balance = 1 * XRT;
+    pub const MaxFieldLength: u32 = 64;
+}
+
+impl identity::Trait for Runtime {
+    type Currency = Balances;
+    type IdentityDeposit = IdentityDeposit;
+    type MaxFieldLength = MaxFieldLength;
+    /// Registrars are appointed by referendum or council majority.
+    type RegistrarOrigin = EnsureRootOrHalfCouncil;
+    type Event = Event;
+}
+
+parameter_types! {
+    pub const ContractTransferFee: Balance = 1 * GLUSHKOV;
+    pub const ContractCreationFee: Balance = 1 * GLUSHKOV;
+    pub const ContractTransactionBaseFee: Balance = 1 * GLUSHKOV;
+    pub const ContractTransactionByteFee: Balance = 10 * COASE;
+    pub const ContractFee: Balance = 1 * GLUSHKOV;
+    pub const TombstoneDeposit: Balance = 1 * XRT;
+    pub const RentByteFee: Balance = 1 * XRT;
+    pub const RentDepositOffset: Balance = 1000 * XRT;
+    pub const SurchargeReward: Balance = 150 * XRT;
+}
+
+/// Contracts use robonomics through `ext_dispatch_call`: dispatched call origin is signed
+/// by contract account, so contract could send demands and offers, and finalize liabilities
+/// where it's set as custodian. Liabilities are read from `LiabilityOf` runtime storage.
+impl contracts::Trait for Runtime {
+    type Currency = Balances;
+    type Call = Call;
+    type Event = Event;
+    type DetermineContractAddress = contracts::SimpleAddressDeterminator<Runtime>;
+    type ComputeDispatchFee = contracts::DefaultDispatchFeeComputor<Runtime>;
+    type TrieIdGenerator = contracts::TrieIdFromParentCounter<Runtime>;
+    type GasPayment = ();
+    type SignedClaimHandicap = contracts::DefaultSignedClaimHandicap;
+    type TombstoneDeposit = TombstoneDeposit;
+    type StorageSizeOffset = contracts::DefaultStorageSizeOffset;
+    type RentByteFee = RentByteFee;
+    type RentDepositOffset = RentDepositOffset;
+    type SurchargeReward = SurchargeReward;
+    type TransferFee = ContractTransferFee;
+    type CreationFee = ContractCreationFee;
+    type TransactionBaseFee = ContractTransactionBaseFee;
+    type TransactionByteFee = ContractTransactionByteFee;
+    type ContractFee = ContractFee;
+    type CallBaseFee = contracts::DefaultCallBaseFee;
+    type CreateBaseFee = contracts::DefaultCreateBaseFee;
+    type MaxDepth = contracts::DefaultMaxDepth;
+    type MaxValueSize = contracts::DefaultMaxValueSize;
+    type BlockGasLimit = contracts::DefaultBlockGasLimit;
 }
 
 construct_runtime!(
@@ -310,6 +492,7 @@ construct_runtime!(
         // Basic stuff.
         System: system::{Module, Call, Storage, Config, Event},
         Timestamp: timestamp::{Module, Call, Storage, Inherent},
+        BlockTime: block_time::{Module, Storage, Config},
 
         // Native currency and accounts.
         Balances: balances,
@@ -319,6 +502,7 @@ construct_runtime!(
         Session: session::{Module, Call, Storage, Event, Config<T>},
         Authorship: authorship::{Module, Call, Storage, Inherent},
         Staking: staking::{default, OfflineWorker},
+        Rewards: rewards::{Module, Storage, Config, Event<T>},
         Offences: offences::{Module, Call, Storage, Event},
         Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
         FinalityTracker: finality_tracker::{Module, Call, Inherent},
@@ -326,11 +510,23 @@ construct_runtime!(
         ImOnline: im_online::{Module, Call, Storage, Event<T>, ValidateUnsigned, Config<T>},
         AuthorityDiscovery: authority_discovery::{Module, Call, Config<T>},
 
+        // Governance.
+        Democracy: democracy::{Module, Call, Storage, Config, Event<T>},
+        Council: collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+        TechnicalCommittee: collective::<Instance2>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+        Treasury: treasury::{Module, Call, Storage, Event<T>},
+
         // Robonomics Network support.
-        Robonomics: robonomics::{Module, Call, Storage, Event<T>},
+        Robonomics: robonomics::{Module, Call, Storage, Event<T>, Config<T>},
+        Sponsorship: sponsorship::{Module, Call, Storage, Event<T>},
+
+        // Shared control of accounts.
+        Proxy: proxy::{Module, Call, Storage, Event<T>},
+        Multisig: multisig::{Module, Call, Storage, Event<T>},
+        Identity: identity::{Module, Call, Storage, Event<T>},
 
-        // Sudo. Usable initially.
-        Sudo: sudo,
+        // Smart contracts.
+        Contracts: contracts,
     }
 );
 
@@ -351,7 +547,9 @@ impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtim
             system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
             system::CheckNonce::<Runtime>::from(index),
             system::CheckWeight::<Runtime>::new(),
-            balances::TakeFees::<Runtime>::from(tip),
+            sponsorship::TakeSponsoredFees::<Runtime>::from(tip),
+            robonomics::CheckRateLimit::<Runtime>::new(),
+            robonomics::CheckLiabilityParty::<Runtime>::new(),
         );
         let raw_payload = SignedPayload::new(call, extra).ok()?;
         let signature = F::sign(account.clone(), &raw_payload)?;
@@ -383,7 +581,9 @@ pub type SignedExtra = (
     system::CheckEra<Runtime>,
     system::CheckNonce<Runtime>,
     system::CheckWeight<Runtime>,
-    balances::TakeFees<Runtime>
+    sponsorship::TakeSponsoredFees<Runtime>,
+    robonomics::CheckRateLimit<Runtime>,
+    robonomics::CheckLiabilityParty<Runtime>
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -492,7 +692,7 @@ impl_runtime_apis! {
                 authorities: Babe::authorities(),
                 epoch_index: Babe::epoch_index(),
                 randomness: Babe::randomness(),
-                duration: EpochDuration::get(),
+                duration: BlockTime::epoch_duration(),
                 secondary_slots: Babe::secondary_slots().0,
             }
         }
@@ -533,6 +733,55 @@ impl_runtime_apis! {
         }
     }
 
+    impl substrate_ros_api::FeeApi<Block, Balance> for Runtime {
+        fn estimate_fee(extrinsic: <Block as BlockT>::Extrinsic) -> substrate_ros_api::FeeEstimate<Balance> {
+            let len = extrinsic.encode().len();
+            let info = extrinsic.function.get_dispatch_info();
+            let tip = extrinsic.signature.as_ref()
+                .map(|(_, _, (_, _, _, _, _, sponsored_fees, _, _))| sponsored_fees.tip())
+                .unwrap_or(0);
+
+            substrate_ros_api::FeeEstimate {
+                weight: info.weight,
+                class: info.class,
+                fee: TransactionFee::convert((len, info, tip)),
+            }
+        }
+    }
+
+    impl types::IdentityApi<Block> for Runtime {
+        fn display_name(account: AccountId) -> Option<Vec<u8>> {
+            Identity::display_name(&account)
+        }
+    }
+
+    impl types::RobonomicsApi<Block> for Runtime {
+        fn liability(index: u64) -> Option<robonomics::Liability<Balance, AccountId>> {
+            Robonomics::liability_of(index)
+        }
+
+        fn liabilities_of(account: AccountId) -> Vec<(u64, robonomics::Liability<Balance, AccountId>)> {
+            Robonomics::liabilities_of(account)
+                .into_iter()
+                .filter_map(|index| Robonomics::liability_of(index).map(|l| (index, l)))
+                .collect()
+        }
+
+        fn open_orders(
+            model: Vec<u8>,
+        ) -> (Vec<robonomics::Demand<Balance, AccountId>>, Vec<robonomics::Offer<Balance, AccountId, BlockNumber>>) {
+            Robonomics::open_orders(model)
+        }
+
+        fn snapshot() -> robonomics::Snapshot<Balance, AccountId, BlockNumber, Hash> {
+            Robonomics::snapshot()
+        }
+
+        fn queue_depth() -> (u64, u64) {
+            Robonomics::queue_depth()
+        }
+    }
+
     impl substrate_session::SessionKeys<Block> for Runtime {
         fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
             let seed = seed.as_ref().map(|s| rstd::str::from_utf8(&s).expect("Seed is an utf8 string"));
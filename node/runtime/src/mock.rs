@@ -0,0 +1,208 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Test runtime of robonomics modules, balances module is the only currency.
+
+use primitives::{H256, Blake2Hasher};
+use support::{impl_outer_origin, impl_outer_dispatch, parameter_types};
+use sr_primitives::{
+    Perbill, Permill,
+    testing::Header,
+    weights::DispatchInfo,
+    traits::{BlakeTwo256, IdentityLookup, ConvertInto, Convert},
+};
+use system::EnsureRoot;
+use crate::{robonomics, sponsorship, proxy, multisig, identity};
+
+impl_outer_origin! {
+    pub enum Origin for Test {}
+}
+
+impl_outer_dispatch! {
+    pub enum OuterCall for Test where origin: Origin {
+        balances::Balances,
+        robonomics::Robonomics,
+        proxy::Proxy,
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: u32 = 1_000_000_000;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::from_percent(100);
+}
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Call = OuterCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type WeightMultiplierUpdate = ();
+    type Event = ();
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 0;
+    pub const TransferFee: u64 = 0;
+    pub const CreationFee: u64 = 0;
+    pub const TransactionBaseFee: u64 = 0;
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type DustRemoval = ();
+    type TransferPayment = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+    type WeightToFee = ConvertInto;
+}
+
+/// Protocol fee destination account.
+pub const TREASURY: u64 = 100;
+
+parameter_types! {
+    pub const ProtocolFee: Permill = Permill::from_percent(10);
+    pub const Treasury: u64 = TREASURY;
+    pub const RateLimitCalls: u32 = 0;
+    pub const RateLimitWindow: u64 = 10;
+    pub const MaxOfferMatches: u32 = 2;
+}
+
+impl robonomics::Trait for Test {
+    type Currency = Balances;
+    type Event = ();
+    type ProtocolFee = ProtocolFee;
+    type Treasury = Treasury;
+    type GovernanceOrigin = EnsureRoot<u64>;
+    type RateLimitCalls = RateLimitCalls;
+    type RateLimitWindow = RateLimitWindow;
+    type MaxOfferMatches = MaxOfferMatches;
+    type WrappedCalls = ProxyCalls;
+}
+
+/// Proxy calls are checked as calls of real account.
+pub struct ProxyCalls;
+
+impl robonomics::UnwrapCall<u64, OuterCall> for ProxyCalls {
+    fn unwrap_call<'a>(who: &u64, call: &'a OuterCall) -> Option<(u64, &'a OuterCall)> {
+        match call {
+            OuterCall::Proxy(proxy::Call::proxy(real, call)) => {
+                let real = if Proxy::is_proxy(real, who) { *real } else { *who };
+                Some((real, &**call))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Transaction fee of test runtime.
+pub const FEE: u64 = 10;
+
+/// Fixed transaction fee plus tip.
+pub struct FixedFee;
+
+impl Convert<(usize, DispatchInfo, u64), u64> for FixedFee {
+    fn convert((_, _, tip): (usize, DispatchInfo, u64)) -> u64 {
+        FEE + tip
+    }
+}
+
+/// Every call could be sponsored.
+pub struct AllCalls;
+
+impl sponsorship::IsSponsoredCall<OuterCall> for AllCalls {
+    fn is_sponsored(_call: &OuterCall) -> bool {
+        true
+    }
+}
+
+impl sponsorship::Trait for Test {
+    type Currency = Balances;
+    type ComputeFee = FixedFee;
+    type TransactionPayment = ();
+    type SponsoredCalls = AllCalls;
+    type Event = ();
+}
+
+impl proxy::Trait for Test {
+    type Call = OuterCall;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const ApprovalDeposit: u64 = 5;
+}
+
+impl multisig::Trait for Test {
+    type Call = balances::Call<Test>;
+    type Currency = Balances;
+    type ApprovalDeposit = ApprovalDeposit;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const IdentityDeposit: u64 = 10;
+    pub const MaxFieldLength: u32 = 16;
+}
+
+impl identity::Trait for Test {
+    type Currency = Balances;
+    type IdentityDeposit = IdentityDeposit;
+    type MaxFieldLength = MaxFieldLength;
+    type RegistrarOrigin = EnsureRoot<u64>;
+    type Event = ();
+}
+
+pub type System = system::Module<Test>;
+pub type Balances = balances::Module<Test>;
+pub type Robonomics = robonomics::Module<Test>;
+pub type Sponsorship = sponsorship::Module<Test>;
+pub type Proxy = proxy::Module<Test>;
+pub type Multisig = multisig::Module<Test>;
+pub type Identity = identity::Module<Test>;
+
+/// Accounts 1, 2 and 3 are endowed, account 4 has no balance.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    balances::GenesisConfig::<Test> {
+        balances: vec![(1, 1000), (2, 1000), (3, 1000)],
+        vesting: vec![],
+    }.assimilate_storage(&mut t).unwrap();
+    t.into()
+}
@@ -0,0 +1,241 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Multisig accounts. Account id is derived from sorted signatories and threshold,
+//! call is dispatched from multisig account when `threshold` signatories approve it.
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use system::ensure_signed;
+use support::{
+    StorageMap, Parameter, decl_module, decl_storage, decl_event, ensure,
+    dispatch::{Result, Dispatchable},
+    traits::{Currency, ReservableCurrency, Get},
+};
+use sr_primitives::{traits::Hash, weights::GetDispatchInfo};
+use crate::proxy::Passthrough;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Maximal count of multisig account signatories.
+pub const MAX_SIGNATORIES: usize = 32;
+
+pub trait Trait: system::Trait {
+    /// The overarching call type.
+    type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+    /// Currency of approval deposit.
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// Deposit reserved from signatory for each pending approval.
+    type ApprovalDeposit: Get<BalanceOf<Self>>;
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin
+    {
+        fn deposit_event() = default;
+
+        /// Approve call of multisig account, dispatch it when `threshold` approvals collected.
+        ///
+        /// Approval deposit is reserved while call is pending and returned when call
+        /// is dispatched or approval is cancelled.
+        #[weight = <Passthrough<<T as Trait>::Call>>::new()]
+        pub fn as_multi(
+            origin,
+            threshold: u16,
+            other_signatories: Vec<T::AccountId>,
+            call: Box<<T as Trait>::Call>
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+            let signatories = Self::signatories(who.clone(), other_signatories)?;
+            ensure!(threshold > 0, "threshold should be positive");
+            ensure!(threshold as usize <= signatories.len(), "threshold exceeds signatories count");
+
+            let id = Self::multi_account_id(&signatories, threshold);
+            let call_hash = T::Hashing::hash_of(&call);
+
+            let mut approvals = Self::approvals(&id, &call_hash);
+            ensure!(!approvals.contains(&who), "call already approved by sender");
+            approvals.push(who.clone());
+
+            if approvals.len() < threshold as usize {
+                T::Currency::reserve(&who, T::ApprovalDeposit::get())
+                    .map_err(|_| "sender's balance too low for approval deposit")?;
+                Self::deposit_event(RawEvent::MultisigApproval(who, id.clone(), call_hash));
+                <Approvals<T>>::insert((id, call_hash), approvals);
+                return Ok(())
+            }
+
+            <Approvals<T>>::remove((id.clone(), call_hash));
+            for approver in approvals.iter().filter(|a| *a != &who) {
+                T::Currency::unreserve(approver, T::ApprovalDeposit::get());
+            }
+            let result = call.dispatch(system::RawOrigin::Signed(id.clone()).into());
+            Self::deposit_event(RawEvent::MultisigExecuted(id, call_hash, result.is_ok()));
+
+            result
+        }
+
+        /// Withdraw approval of multisig account call.
+        pub fn cancel_as_multi(
+            origin,
+            threshold: u16,
+            other_signatories: Vec<T::AccountId>,
+            call_hash: T::Hash
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+            let signatories = Self::signatories(who.clone(), other_signatories)?;
+            let id = Self::multi_account_id(&signatories, threshold);
+
+            let mut approvals = Self::approvals(&id, &call_hash);
+            ensure!(approvals.contains(&who), "call isn't approved by sender");
+            approvals.retain(|a| a != &who);
+            T::Currency::unreserve(&who, T::ApprovalDeposit::get());
+
+            if approvals.is_empty() {
+                <Approvals<T>>::remove((id.clone(), call_hash));
+            } else {
+                <Approvals<T>>::insert((id.clone(), call_hash), approvals);
+            }
+            Self::deposit_event(RawEvent::MultisigCancelled(who, id, call_hash));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Multisig {
+        /// Signatories approved multisig account call with given hash.
+        pub Approvals: map (T::AccountId, T::Hash) => Vec<T::AccountId>;
+    }
+}
+
+decl_event! {
+    pub enum Event<T>
+        where <T as system::Trait>::AccountId,
+              <T as system::Trait>::Hash
+    {
+        /// Signatory approved multisig account call.
+        MultisigApproval(AccountId, AccountId, Hash),
+
+        /// Signatory withdrawn approval of multisig account call.
+        MultisigCancelled(AccountId, AccountId, Hash),
+
+        /// Multisig account call dispatched with given result.
+        MultisigExecuted(AccountId, Hash, bool),
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Derive multisig account id from sorted signatories and threshold.
+    pub fn multi_account_id(signatories: &[T::AccountId], threshold: u16) -> T::AccountId {
+        let entropy = (b"robonomics/multisig", signatories, threshold).using_encoded(T::Hashing::hash);
+        T::AccountId::decode(&mut &entropy.encode()[..]).unwrap_or_default()
+    }
+
+    /// Approvals of multisig account call.
+    pub fn approvals(id: &T::AccountId, call_hash: &T::Hash) -> Vec<T::AccountId> {
+        <Approvals<T>>::get((id.clone(), call_hash.clone()))
+    }
+
+    /// Sorted list of signatories, sender included.
+    pub fn signatories(
+        who: T::AccountId,
+        mut other_signatories: Vec<T::AccountId>
+    ) -> rstd::result::Result<Vec<T::AccountId>, &'static str> {
+        other_signatories.push(who);
+        other_signatories.sort();
+        other_signatories.dedup();
+        ensure!(other_signatories.len() > 1, "at least two signatories required");
+        ensure!(other_signatories.len() <= MAX_SIGNATORIES, "too many signatories");
+        Ok(other_signatories)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{assert_ok, assert_noop};
+    use crate::mock::*;
+
+    fn transfer(dest: u64, value: u64) -> Box<balances::Call<Test>> {
+        Box::new(balances::Call::transfer(dest, value))
+    }
+
+    #[test]
+    fn approval_deposit_is_returned_on_execution() {
+        with_externalities(&mut new_test_ext(), || {
+            let id = Multisig::multi_account_id(&[1, 2], 2);
+            let _ = Balances::deposit_creating(&id, 100);
+
+            assert_ok!(Multisig::as_multi(Origin::signed(1), 2, vec![2], transfer(3, 10)));
+            assert_eq!(Balances::reserved_balance(&1), 5);
+            assert_noop!(
+                Multisig::as_multi(Origin::signed(1), 2, vec![2], transfer(3, 10)),
+                "call already approved by sender"
+            );
+
+            assert_ok!(Multisig::as_multi(Origin::signed(2), 2, vec![1], transfer(3, 10)));
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert_eq!(Balances::reserved_balance(&2), 0);
+            assert_eq!(Balances::free_balance(&id), 90);
+            assert_eq!(Balances::free_balance(&3), 1010);
+        });
+    }
+
+    #[test]
+    fn cancel_returns_approval_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            let call = transfer(3, 10);
+            let call_hash = <Test as system::Trait>::Hashing::hash_of(&call);
+            let id = Multisig::multi_account_id(&[1, 2, 3], 2);
+
+            assert_ok!(Multisig::as_multi(Origin::signed(1), 2, vec![2, 3], call));
+            assert_eq!(Multisig::approvals(&id, &call_hash), vec![1]);
+
+            assert_noop!(
+                Multisig::cancel_as_multi(Origin::signed(2), 2, vec![1, 3], call_hash),
+                "call isn't approved by sender"
+            );
+            assert_ok!(Multisig::cancel_as_multi(Origin::signed(1), 2, vec![2, 3], call_hash));
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert!(Multisig::approvals(&id, &call_hash).is_empty());
+        });
+    }
+
+    #[test]
+    fn pending_approval_requires_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Multisig::as_multi(Origin::signed(4), 2, vec![1], transfer(3, 10)),
+                "sender's balance too low for approval deposit"
+            );
+        });
+    }
+
+    #[test]
+    fn multisig_weight_includes_wrapped_call() {
+        let call = transfer(3, 10);
+        let info = call.get_dispatch_info();
+        let multisig_info = Call::<Test>::as_multi(2, vec![2], call).get_dispatch_info();
+        assert!(multisig_info.weight > info.weight);
+        assert_eq!(multisig_info.class, info.class);
+    }
+}
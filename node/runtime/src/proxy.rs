@@ -0,0 +1,177 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Proxy accounts. Real account (e.g. robot operator) allows proxy accounts to send
+//! calls on its behalf, dispatched call origin is signed by real account.
+
+use rstd::{prelude::*, marker::PhantomData};
+use system::ensure_signed;
+use support::{
+    StorageMap, Parameter, decl_module, decl_storage, decl_event, ensure,
+    dispatch::{Result, Dispatchable},
+};
+use sr_primitives::weights::{
+    Weight, WeighData, ClassifyDispatch, DispatchClass, GetDispatchInfo,
+};
+
+/// Weight of call wrapper itself.
+const WRAPPER_WEIGHT: Weight = 10_000;
+
+/// Dispatch weight of call wrapper: weight of wrapped call plus wrapper weight,
+/// dispatch class of wrapped call. Wrapped call is the last call argument.
+pub struct Passthrough<Call>(PhantomData<Call>);
+
+impl<Call> Passthrough<Call> {
+    pub fn new() -> Self {
+        Passthrough(PhantomData)
+    }
+}
+
+impl<'a, A, Call: GetDispatchInfo> WeighData<(&'a A, &'a Box<Call>)> for Passthrough<Call> {
+    fn weigh_data(&self, (_, call): (&'a A, &'a Box<Call>)) -> Weight {
+        call.get_dispatch_info().weight.saturating_add(WRAPPER_WEIGHT)
+    }
+}
+
+impl<'a, A, Call: GetDispatchInfo> ClassifyDispatch<(&'a A, &'a Box<Call>)> for Passthrough<Call> {
+    fn classify_dispatch(&self, (_, call): (&'a A, &'a Box<Call>)) -> DispatchClass {
+        call.get_dispatch_info().class
+    }
+}
+
+impl<'a, A, B, Call: GetDispatchInfo> WeighData<(&'a A, &'a B, &'a Box<Call>)> for Passthrough<Call> {
+    fn weigh_data(&self, (_, _, call): (&'a A, &'a B, &'a Box<Call>)) -> Weight {
+        call.get_dispatch_info().weight.saturating_add(WRAPPER_WEIGHT)
+    }
+}
+
+impl<'a, A, B, Call: GetDispatchInfo> ClassifyDispatch<(&'a A, &'a B, &'a Box<Call>)> for Passthrough<Call> {
+    fn classify_dispatch(&self, (_, _, call): (&'a A, &'a B, &'a Box<Call>)) -> DispatchClass {
+        call.get_dispatch_info().class
+    }
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching call type.
+    type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin
+    {
+        fn deposit_event() = default;
+
+        /// Allow account to send calls on behalf of sender.
+        pub fn add_proxy(origin, proxy: T::AccountId) -> Result {
+            let real = ensure_signed(origin)?;
+            ensure!(!Self::is_proxy(&real, &proxy), "proxy already added");
+
+            <Proxies<T>>::mutate(&real, |v| v.push(proxy.clone()));
+            Self::deposit_event(RawEvent::ProxyAdded(real, proxy));
+
+            Ok(())
+        }
+
+        /// Disallow account to send calls on behalf of sender.
+        pub fn remove_proxy(origin, proxy: T::AccountId) -> Result {
+            let real = ensure_signed(origin)?;
+            ensure!(Self::is_proxy(&real, &proxy), "proxy not found");
+
+            <Proxies<T>>::mutate(&real, |v| v.retain(|p| p != &proxy));
+            Self::deposit_event(RawEvent::ProxyRemoved(real, proxy));
+
+            Ok(())
+        }
+
+        /// Dispatch call on behalf of real account.
+        #[weight = <Passthrough<<T as Trait>::Call>>::new()]
+        pub fn proxy(origin, real: T::AccountId, call: Box<<T as Trait>::Call>) -> Result {
+            let proxy = ensure_signed(origin)?;
+            ensure!(Self::is_proxy(&real, &proxy), "sender isn't proxy of real account");
+
+            let result = call.dispatch(system::RawOrigin::Signed(real.clone()).into());
+            Self::deposit_event(RawEvent::ProxyExecuted(real, proxy, result.is_ok()));
+
+            result
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Proxy {
+        /// Proxy accounts of real account.
+        pub Proxies get(proxies): map T::AccountId => Vec<T::AccountId>;
+    }
+}
+
+decl_event! {
+    pub enum Event<T> where <T as system::Trait>::AccountId {
+        /// Real account added proxy.
+        ProxyAdded(AccountId, AccountId),
+
+        /// Real account removed proxy.
+        ProxyRemoved(AccountId, AccountId),
+
+        /// Proxy dispatched call on behalf of real account with given result.
+        ProxyExecuted(AccountId, AccountId, bool),
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Returns `true` when account is allowed to send calls on behalf of real account.
+    pub fn is_proxy(real: &T::AccountId, who: &T::AccountId) -> bool {
+        Self::proxies(real).contains(who)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{assert_ok, assert_noop};
+    use crate::mock::*;
+
+    fn transfer(dest: u64, value: u64) -> Box<OuterCall> {
+        Box::new(OuterCall::Balances(balances::Call::transfer(dest, value)))
+    }
+
+    #[test]
+    fn proxy_weight_includes_wrapped_call() {
+        let call = transfer(3, 10);
+        let info = call.get_dispatch_info();
+        let proxy_info = Call::<Test>::proxy(1, call).get_dispatch_info();
+        assert_eq!(proxy_info.weight, info.weight + WRAPPER_WEIGHT);
+        assert_eq!(proxy_info.class, info.class);
+    }
+
+    #[test]
+    fn proxy_dispatches_on_behalf_of_real_account() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(Proxy::proxy(Origin::signed(2), 1, transfer(3, 10)), "sender isn't proxy of real account");
+
+            assert_ok!(Proxy::add_proxy(Origin::signed(1), 2));
+            assert_ok!(Proxy::proxy(Origin::signed(2), 1, transfer(3, 10)));
+            assert_eq!(Balances::free_balance(&1), 990);
+            assert_eq!(Balances::free_balance(&3), 1010);
+
+            assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2));
+            assert!(!Proxy::is_proxy(&1, &2));
+        });
+    }
+}
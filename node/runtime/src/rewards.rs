@@ -0,0 +1,215 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Inflationary validator rewards. Block authors earn points, at the end of each session
+//! minted XRT are distributed across validators by points. Validator takes its
+//! `validator_payment` (see `staking::ValidatorPrefs`) up-front, the rest is shared with
+//! nominators proportionally to their exposure.
+//!
+//! Yearly inflation depends on staked part of total issuance: it grows linearly from
+//! `min_inflation` to `max_inflation` while stake approaches `ideal_stake` and falls back
+//! to `min_inflation` when all tokens are staked. Curve parameters are set in chain spec.
+//!
+//! Authorship events are passed to staking as well, so era points are recorded there.
+//! Rewards are paid to destination chosen by staker (`staking::Payee`).
+
+use rstd::prelude::*;
+use support::{
+    StorageValue, StorageMap, decl_module, decl_storage, decl_event,
+    traits::{Currency, Time},
+};
+use staking::RewardDestination;
+use sr_primitives::{Perbill, traits::{Zero, Saturating, SaturatedConversion}};
+use sr_staking_primitives::SessionIndex;
+
+type BalanceOf<T> =
+    <<T as staking::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Milliseconds in a year of 365.25 days.
+const MILLISECS_PER_YEAR: u64 = 1000 * 3600 * 24 * 36525 / 100;
+
+/// Points for authoring a block.
+const AUTHOR_POINTS: u32 = 20;
+/// Points for referencing an uncle block.
+const UNCLE_REFERENCE_POINTS: u32 = 2;
+/// Points for authoring a referenced uncle.
+const UNCLE_AUTHOR_POINTS: u32 = 1;
+
+pub trait Trait: staking::Trait + authorship::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Time used to compute session length.
+    type Time: Time;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event() = default;
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Rewards {
+        /// Staked part of total issuance giving maximal inflation.
+        pub IdealStake get(ideal_stake) config(): Perbill = Perbill::from_percent(50);
+
+        /// Yearly inflation when nothing or everything is staked.
+        pub MinInflation get(min_inflation) config(): Perbill = Perbill::from_percent(2);
+
+        /// Yearly inflation when ideal stake is reached.
+        pub MaxInflation get(max_inflation) config(): Perbill = Perbill::from_percent(10);
+
+        /// Points earned by validators during current session.
+        pub SessionPoints get(session_points): map T::AccountId => u32;
+
+        /// Sum of points earned during current session.
+        pub TotalPoints get(total_points): u32;
+
+        /// Validators earned points during current session.
+        pub Authors get(authors): Vec<T::AccountId>;
+
+        /// Time of the latest payout.
+        pub LastPayout get(last_payout): u64;
+    }
+}
+
+decl_event! {
+    pub enum Event<T>
+        where <T as system::Trait>::AccountId,
+              Balance = BalanceOf<T>
+    {
+        /// Tokens minted at the end of session.
+        SessionReward(SessionIndex, Balance),
+
+        /// Validator or nominator rewarded.
+        Rewarded(AccountId, Balance),
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Yearly inflation for given staked part of total issuance.
+    pub fn inflation(stake: Perbill) -> Perbill {
+        const ONE: u64 = 1_000_000_000;
+        let min = Self::min_inflation().deconstruct() as u64;
+        let max = Self::max_inflation().deconstruct() as u64;
+        let ideal = Self::ideal_stake().deconstruct() as u64;
+        let x = stake.deconstruct() as u64;
+
+        let delta = max.saturating_sub(min);
+        let rate = if x <= ideal {
+            if ideal == 0 { max } else { min + delta * x / ideal }
+        } else {
+            min + delta * (ONE - x) / (ONE - ideal)
+        };
+
+        Perbill::from_parts(rate as u32)
+    }
+
+    fn add_points(who: T::AccountId, points: u32) {
+        if !<SessionPoints<T>>::exists(&who) {
+            <Authors<T>>::mutate(|v| v.push(who.clone()));
+        }
+        <SessionPoints<T>>::mutate(&who, |v| *v = v.saturating_add(points));
+        TotalPoints::mutate(|v| *v = v.saturating_add(points));
+    }
+
+    /// Pay reward of staker according to its reward destination.
+    fn reward(stash: &T::AccountId, amount: BalanceOf<T>) {
+        if amount.is_zero() {
+            return
+        }
+        let payee = <staking::Module<T>>::payee(stash);
+        let dest = match payee {
+            RewardDestination::Controller => <staking::Module<T>>::bonded(stash).unwrap_or_else(|| stash.clone()),
+            RewardDestination::Stash | RewardDestination::Staked => stash.clone(),
+        };
+        // Imbalance increases total issuance when dropped.
+        let _ = T::Currency::deposit_creating(&dest, amount);
+        if payee == RewardDestination::Staked {
+            let _ = <staking::Module<T>>::bond_extra(system::RawOrigin::Signed(stash.clone()).into(), amount);
+        }
+        Self::deposit_event(RawEvent::Rewarded(stash.clone(), amount));
+    }
+
+    /// Mint and distribute rewards for the session passed since latest payout.
+    fn payout(session: SessionIndex) {
+        let now = <T as Trait>::Time::now().saturated_into::<u64>();
+        let last = LastPayout::mutate(|v| rstd::mem::replace(v, now));
+        let elapsed = now.saturating_sub(last);
+
+        let authors = <Authors<T>>::take();
+        let total_points = TotalPoints::take();
+        let points = authors.iter().map(|v| <SessionPoints<T>>::take(v)).collect::<Vec<_>>();
+        // Nothing to pay for the first session, its start time is unknown
+        if last == 0 || total_points == 0 || elapsed == 0 {
+            return
+        }
+
+        let validators = <staking::Module<T>>::current_elected();
+        let total_stake = validators.iter()
+            .map(|v| <staking::Module<T>>::stakers(v).total)
+            .fold(<BalanceOf<T>>::zero(), |a, b| a.saturating_add(b));
+        let issuance = T::Currency::total_issuance();
+        let stake = Perbill::from_rational_approximation(total_stake, issuance);
+
+        let session_part = Perbill::from_rational_approximation(elapsed.min(MILLISECS_PER_YEAR), MILLISECS_PER_YEAR);
+        let total_payout = session_part * (Self::inflation(stake) * issuance);
+
+        for (validator, p) in authors.iter().zip(points.into_iter()) {
+            let reward = Perbill::from_rational_approximation(p, total_points) * total_payout;
+            let exposure = <staking::Module<T>>::stakers(validator);
+
+            // Validator payment is taken off the table, only the rest is shared
+            let validator_payment = reward.min(<staking::Module<T>>::validators(validator).validator_payment);
+            let shared = reward.saturating_sub(validator_payment);
+
+            let mut nominators_reward = <BalanceOf<T>>::zero();
+            if !exposure.total.is_zero() {
+                for nominator in exposure.others.iter() {
+                    let share = Perbill::from_rational_approximation(nominator.value, exposure.total) * shared;
+                    nominators_reward = nominators_reward.saturating_add(share);
+                    Self::reward(&nominator.who, share);
+                }
+            }
+            Self::reward(validator, reward.saturating_sub(nominators_reward));
+        }
+
+        Self::deposit_event(RawEvent::SessionReward(session, total_payout));
+    }
+}
+
+/// Count session points and pass authorship events to staking.
+impl<T: Trait> authorship::EventHandler<T::AccountId, T::BlockNumber> for Module<T> {
+    fn note_author(author: T::AccountId) {
+        <staking::Module<T> as authorship::EventHandler<_, _>>::note_author(author.clone());
+        Self::add_points(author, AUTHOR_POINTS);
+    }
+
+    fn note_uncle(author: T::AccountId, age: T::BlockNumber) {
+        <staking::Module<T> as authorship::EventHandler<_, _>>::note_uncle(author.clone(), age);
+        Self::add_points(<authorship::Module<T>>::author(), UNCLE_REFERENCE_POINTS);
+        Self::add_points(author, UNCLE_AUTHOR_POINTS);
+    }
+}
+
+/// Pay session rewards and pass session ending to staking.
+impl<T: Trait> session::OnSessionEnding<T::AccountId> for Module<T> {
+    fn on_session_ending(ending: SessionIndex, start_session: SessionIndex) -> Option<Vec<T::AccountId>> {
+        Self::payout(ending);
+        <staking::Module<T> as session::OnSessionEnding<T::AccountId>>::on_session_ending(ending, start_session)
+    }
+}
@@ -16,18 +16,38 @@
 //
 ///////////////////////////////////////////////////////////////////////////////
 //! The Robonomics substrate module. This can be compiled with `#[no_std]`, ready for Wasm.
+//!
+//! ## Smart contracts
+//!
+//! Contract could act as liability party or custodian:
+//! - robonomics calls encoded as runtime `Call` are sent by `ext_dispatch_call`, the origin
+//!   of dispatched call is signed by contract account;
+//! - liability is read by `ext_get_runtime_storage` with key
+//!   `blake2_256(b"Robonomics LiabilityOf" ++ index.encode())`.
+//!
+//! Rate limit is checked on dispatch of `demand` and `offer` for origin account, so it's
+//! applied to contracts and to calls wrapped by proxy or multisig as well.
 
-use rstd::vec::Vec;
+use rstd::{vec::Vec, marker::PhantomData};
 #[cfg(feature = "std")]
 use serde_derive::{Serialize, Deserialize};
 use codec::{Encode, Decode};
 use system::ensure_signed;
 use support::{
-    StorageValue, StorageMap,
+    StorageValue, StorageMap, EnumerableStorageMap,
     decl_module, decl_storage, decl_event, ensure,
-    traits::{ReservableCurrency, Currency}, dispatch::Result
+    traits::{ReservableCurrency, Currency, Get, EnsureOrigin},
+    dispatch::{Result, IsSubType},
 };
-use sr_primitives::traits::Hash;
+use sr_primitives::{
+    Permill,
+    weights::{DispatchInfo, SimpleDispatchInfo},
+    traits::{Hash, BlakeTwo256, Zero, One, Saturating, SignedExtension},
+    transaction_validity::{
+        TransactionValidity, ValidTransaction, TransactionValidityError, InvalidTransaction,
+    },
+};
+use crate::constants::validity::{RATE_LIMIT_EXCEEDED, NOT_LIABILITY_PARTY};
 
 /// Order params.
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
@@ -39,12 +59,55 @@ pub struct Order<Balance,AccountId> {
     pub custodian: Option<AccountId>,
 }
 
+/// How many times an offer could be matched with demands.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+pub enum Capacity {
+    /// Offer could be matched given number of times.
+    Limited(u32),
+    /// Offer could be matched while it's valid.
+    Unlimited,
+}
+
+impl Capacity {
+    /// Capacity left after one match, `None` when offer is exhausted.
+    pub fn consume(self) -> Option<Self> {
+        match self {
+            Capacity::Limited(n) if n > 1 => Some(Capacity::Limited(n - 1)),
+            Capacity::Limited(_) => None,
+            Capacity::Unlimited => Some(Capacity::Unlimited),
+        }
+    }
+}
+
+impl Default for Capacity {
+    fn default() -> Self { Capacity::Limited(1) }
+}
+
 /// Offer message.
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 #[derive(Encode, Decode, Clone, PartialEq, Eq)]
-pub struct Offer<Balance,AccountId> {
+pub struct Offer<Balance,AccountId,BlockNumber> {
     pub order: Order<Balance,AccountId>,
     pub sender: AccountId,
+    /// Remaining number of matches.
+    pub capacity: Capacity,
+    /// Offer can't be matched after this block.
+    pub expires: Option<BlockNumber>,
+}
+
+impl<Balance, AccountId, BlockNumber: PartialOrd> Offer<Balance, AccountId, BlockNumber> {
+    /// Returns `true` when offer could not be matched at given block.
+    pub fn is_expired(&self, now: BlockNumber) -> bool {
+        self.expires.as_ref().map_or(false, |expires| now > *expires)
+    }
+}
+
+/// Offer message stored before capacity and validity were added.
+#[derive(Encode, Decode)]
+struct LegacyOffer<Balance,AccountId> {
+    order: Order<Balance,AccountId>,
+    sender: AccountId,
 }
 
 /// Demand message.
@@ -66,17 +129,60 @@ pub struct Liability<Balance,AccountId> {
     pub result: Option<Vec<u8>>
 }
 
+impl<Balance, AccountId: PartialEq> Liability<Balance, AccountId> {
+    /// Returns `true` when account is allowed to finalize liability:
+    /// custodian when given, promisor otherwise.
+    pub fn is_finalizer(&self, who: &AccountId) -> bool {
+        match self.order.custodian {
+            Some(ref custodian) => custodian == who,
+            None => &self.promisor == who,
+        }
+    }
+}
+
+/// Robonomics state at some block: liabilities and orders waiting for matching.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct Snapshot<Balance,AccountId,BlockNumber,Hash> {
+    pub liability_count: u64,
+    pub liabilities: Vec<(u64, Liability<Balance,AccountId>)>,
+    pub demands: Vec<(Hash, Vec<Demand<Balance,AccountId>>)>,
+    pub offers: Vec<(Hash, Vec<Offer<Balance,AccountId,BlockNumber>>)>,
+}
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
+type OfferFor<T> = Offer<BalanceOf<T>,<T as system::Trait>::AccountId,<T as system::Trait>::BlockNumber>;
+
 /// Type used for storing an liability's index; implies the maximum number of liabilities
 /// the system can hold.
 type LiabilityIndex = u64;
 
+/// Calls that dispatch wrapped call on behalf of another account, e.g. proxy calls.
+pub trait UnwrapCall<AccountId, Call> {
+    /// Returns wrapped call and account it is dispatched on behalf of when sent by `who`.
+    fn unwrap_call<'a>(who: &AccountId, call: &'a Call) -> Option<(AccountId, &'a Call)>;
+}
+
 pub trait Trait: system::Trait {
     /// Payment currency; implies the processing token for liability contract.
 	type Currency: ReservableCurrency<Self::AccountId>;
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Default part of liability cost charged as protocol fee.
+    type ProtocolFee: Get<Permill>;
+    /// Protocol fee destination account.
+    type Treasury: Get<Self::AccountId>;
+    /// Origin allowed to change module parameters.
+    type GovernanceOrigin: EnsureOrigin<Self::Origin>;
+    /// Default count of demands and offers account could send during rate limit window.
+    type RateLimitCalls: Get<u32>;
+    /// Default length of rate limit window in blocks.
+    type RateLimitWindow: Get<Self::BlockNumber>;
+    /// Maximal count of pending demands processed by single offer.
+    type MaxOfferMatches: Get<u32>;
+    /// Call wrappers unwrapped by signed extensions before checks.
+    type WrappedCalls: UnwrapCall<Self::AccountId, <Self as system::Trait>::Call>;
 }
 
 decl_module! {
@@ -84,6 +190,11 @@ decl_module! {
     {
         fn deposit_event() = default;
 
+        fn on_initialize(now: T::BlockNumber) {
+            Self::prune_recent_calls(now);
+            Self::match_pending_demands();
+        }
+
         /// Send demand and create liability when matched.
         pub fn demand(
             origin,
@@ -94,43 +205,71 @@ decl_module! {
         ) -> Result {
             // Ensure we have a signed message, and derive the sender's account id from the signature
             let sender = ensure_signed(origin)?;
-            let order = Order { model, objective, cost, custodian }; 
+            Self::check_rate_limit(&sender)?;
+
+            let order = Order { model: model.clone(), objective, cost, custodian }; 
             let order_hash = T::Hashing::hash_of(&order);
             let demand = Demand { order, sender };
 
-            if let Some(offer) = <OfferOf<T>>::mutate(order_hash, |v| v.pop()) {
-                Self::create_liability(demand, offer)
+            Self::migrate_offers(order_hash);
+            Self::remove_expired_offers(order_hash);
+            if let Some(offer) = <OfferOf<T>>::get(order_hash).last().cloned() {
+                Self::create_liability(demand, offer.clone())?;
+                Self::consume_offer(order_hash, offer);
             } else {
                 Self::deposit_event(RawEvent::NewDemand(order_hash.clone(), demand.clone()));
                 <DemandOf<T>>::mutate(order_hash, |v| v.push(demand));
-                Ok(())
             }
+            Self::update_orders_of(&model, order_hash);
+
+            Ok(())
         }
         
         /// Send offer and create liability when matched.
+        ///
+        /// Offer stays in storage and could be matched `capacity` times during
+        /// `validity` blocks (forever when not given). At most `MaxOfferMatches` pending
+        /// demands are matched by the call, the rest is matched on next blocks.
         pub fn offer(
             origin,
             model: Vec<u8>,
             objective: Vec<u8>,
             #[compact] cost: BalanceOf<T>,
-            custodian: Option<T::AccountId>
+            custodian: Option<T::AccountId>,
+            capacity: Capacity,
+            validity: Option<T::BlockNumber>
         ) -> Result {
             // Ensure we have a signed message, and derive the sender's account id from the signature
             let sender = ensure_signed(origin)?;
-            let order = Order { model, objective, cost, custodian }; 
+            ensure!(capacity != Capacity::Limited(0), "offer capacity should be positive");
+            Self::check_rate_limit(&sender)?;
+
+            let order = Order { model: model.clone(), objective, cost, custodian }; 
             let order_hash = T::Hashing::hash_of(&order);
-            let offer = Offer { order, sender };
+            let expires = validity.map(|v| <system::Module<T>>::block_number().saturating_add(v));
+            let mut offer = Offer { order, sender, capacity, expires };
 
-            if let Some(demand) = <DemandOf<T>>::mutate(order_hash, |v| v.pop()) {
-                Self::create_liability(demand, offer)
-            } else {
+            // Count of processed demands is bounded to keep call weight fixed
+            Self::migrate_offers(order_hash);
+            let (is_open, _) = Self::match_demands(order_hash, &mut offer, T::MaxOfferMatches::get());
+            if is_open {
                 Self::deposit_event(RawEvent::NewOffer(order_hash.clone(), offer.clone()));
                 <OfferOf<T>>::mutate(order_hash, |v| v.push(offer));
-                Ok(())
+                if !<DemandOf<T>>::get(order_hash).is_empty() {
+                    // Matching limit is reached, the rest of demands is matched on next blocks
+                    <PendingMatches<T>>::mutate(|v| if !v.contains(&order_hash) { v.push(order_hash) });
+                }
             }
+            Self::update_orders_of(&model, order_hash);
+
+            Ok(())
         }
 
         /// Send result to finalize liability.
+        ///
+        /// Operational dispatch: could be included in congested blocks, only liability
+        /// party (custodian when given, promisor otherwise) is allowed to send it.
+        #[weight = SimpleDispatchInfo::FixedOperational(10_000)]
         pub fn finalize(
             origin,
             liability_index: LiabilityIndex,
@@ -142,12 +281,24 @@ decl_module! {
 
             // Safety checks
             ensure!(None == liability.result, "liability already finalized");
-            if let Some(custodian) = liability.clone().order.custodian {
-                ensure!(sender == custodian, "this call is for custodian only");
-            }
+            ensure!(liability.is_finalizer(&sender), "sender isn't liability custodian or promisor");
 
-            // Release costs
-		    T::Currency::repatriate_reserved(&liability.promisee, &liability.promisor, liability.order.cost)?;
+            ensure!(
+                T::Currency::reserved_balance(&liability.promisee) >= liability.order.cost,
+                "promisee's reserved balance too low"
+            );
+
+            // Release costs, reserved balance is checked above so transfers can't fail
+            // and promisor or treasury accounts are created when not exist
+            let fee = Self::protocol_fee() * liability.order.cost;
+            let payment = liability.order.cost.saturating_sub(fee);
+            let (imbalance, _) = T::Currency::slash_reserved(&liability.promisee, payment);
+            T::Currency::resolve_creating(&liability.promisor, imbalance);
+            if !fee.is_zero() {
+                let (imbalance, _) = T::Currency::slash_reserved(&liability.promisee, fee);
+                T::Currency::resolve_creating(&T::Treasury::get(), imbalance);
+                Self::deposit_event(RawEvent::FeeCollected(liability_index, fee));
+            }
 
             // Update storage
             <LiabilityOf<T>>::insert(liability_index, Liability { result: Some(result.clone()), .. liability });
@@ -155,6 +306,21 @@ decl_module! {
 
             Ok(())
         }
+
+        /// Set part of liability cost charged as protocol fee.
+        pub fn set_protocol_fee(origin, fee: Permill) -> Result {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            FeeRate::put(fee);
+            Ok(())
+        }
+
+        /// Allow each account to send `calls` demands and offers per `window` blocks,
+        /// zero `calls` disables rate limiting.
+        pub fn set_rate_limit(origin, calls: u32, window: T::BlockNumber) -> Result {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+            <RateLimit<T>>::put((calls, window));
+            Ok(())
+        }
     }
 }
 
@@ -166,13 +332,70 @@ decl_storage! {
 
         /// Get offer by hash.
         pub OfferOf get(offer_of):
-            map T::Hash => Vec<Offer<BalanceOf<T>,T::AccountId>>;
+            map T::Hash => Vec<OfferFor<T>>;
 
         pub LiabilityCount get(liability_count): LiabilityIndex;
 
         /// Get liability by index.
         pub LiabilityOf get(liability_of):
             map LiabilityIndex => Option<Liability<BalanceOf<T>,T::AccountId>>;
+
+        /// Get indexes of liabilities where account is promisee or promisor.
+        pub LiabilitiesOf get(liabilities_of): map T::AccountId => Vec<LiabilityIndex>;
+
+        /// Get hashes of orders with demands or offers waiting for matching by model.
+        pub OrdersOf get(orders_of): linked_map Vec<u8> => Vec<T::Hash>;
+
+        /// Protocol fee set by governance, overrides `Trait::ProtocolFee` when present.
+        pub FeeRate get(fee_rate): Option<Permill>;
+
+        /// Rate limit set by governance, overrides `Trait::RateLimitCalls` and
+        /// `Trait::RateLimitWindow` when present.
+        pub RateLimit get(rate_limit): Option<(u32, T::BlockNumber)>;
+
+        /// Blocks of recent demands and offers sent by account.
+        pub RecentCalls get(recent_calls): map T::AccountId => Vec<T::BlockNumber>;
+
+        /// Accounts with recent calls to prune at given block.
+        ExpiringCalls: map T::BlockNumber => Vec<T::AccountId>;
+
+        /// Hashes of orders with demands left by offer that reached `MaxOfferMatches`.
+        pub PendingMatches get(pending_matches): Vec<T::Hash>;
+    }
+    add_extra_genesis {
+        /// Liabilities with indexes, cost of not finalized ones is reserved from promisee.
+        /// Liability could not be finalized when promisee balance is too low to reserve
+        /// its cost, `import-liabilities` checks balances of seeded chain spec.
+        config(liabilities): Vec<(LiabilityIndex, Liability<BalanceOf<T>,T::AccountId>)>;
+        /// Demands waiting for matching.
+        config(demands): Vec<Demand<BalanceOf<T>,T::AccountId>>;
+        /// Offers waiting for matching.
+        config(offers): Vec<OfferFor<T>>;
+        build(|config: &GenesisConfig<T>| {
+            for (index, liability) in config.liabilities.iter().cloned() {
+                if liability.result.is_none() {
+                    let _ = T::Currency::reserve(&liability.promisee, liability.order.cost);
+                }
+                <LiabilitiesOf<T>>::mutate(&liability.promisee, |v| v.push(index));
+                if liability.promisor != liability.promisee {
+                    <LiabilitiesOf<T>>::mutate(&liability.promisor, |v| v.push(index));
+                }
+                <LiabilityOf<T>>::insert(index, liability);
+                LiabilityCount::mutate(|v| *v = (*v).max(index + 1));
+            }
+            for demand in config.demands.iter().cloned() {
+                let order_hash = T::Hashing::hash_of(&demand.order);
+                let model = demand.order.model.clone();
+                <DemandOf<T>>::mutate(order_hash, |v| v.push(demand));
+                <Module<T>>::update_orders_of(&model, order_hash);
+            }
+            for offer in config.offers.iter().cloned() {
+                let order_hash = T::Hashing::hash_of(&offer.order);
+                let model = offer.order.model.clone();
+                <OfferOf<T>>::mutate(order_hash, |v| v.push(offer));
+                <Module<T>>::update_orders_of(&model, order_hash);
+            }
+        });
     }
 }
 
@@ -180,34 +403,105 @@ decl_event! {
     pub enum Event<T>
         where <T as system::Trait>::Hash,
               <T as system::Trait>::AccountId,
+              <T as system::Trait>::BlockNumber,
               Balance = BalanceOf<T>
     {
         /// Someone wants a service.
         NewDemand(Hash, Demand<Balance, AccountId>),
 
         /// Someone provide a service.
-        NewOffer(Hash, Offer<Balance, AccountId>),
+        NewOffer(Hash, Offer<Balance, AccountId, BlockNumber>),
+
+        /// Demand is removed from storage because promisee can't pay liability cost.
+        DemandClosed(Hash, Demand<Balance, AccountId>),
+
+        /// Offer is exhausted or expired and removed from storage.
+        OfferClosed(Hash, Offer<Balance, AccountId, BlockNumber>),
 
         /// Yay! New liability created.
         NewLiability(LiabilityIndex, Liability<Balance, AccountId>),
 
         /// Result published.
         Finalized(LiabilityIndex, Vec<u8>),
+
+        /// Protocol fee sent to treasury on liability finalization.
+        FeeCollected(LiabilityIndex, Balance),
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// Part of liability cost currently charged as protocol fee.
+    pub fn protocol_fee() -> Permill {
+        Self::fee_rate().unwrap_or_else(T::ProtocolFee::get)
+    }
+
+    /// Count of calls and window length of current rate limit.
+    pub fn current_rate_limit() -> (u32, T::BlockNumber) {
+        Self::rate_limit().unwrap_or_else(|| (T::RateLimitCalls::get(), T::RateLimitWindow::get()))
+    }
+
+    /// Blocks of account calls that are still inside of rate limit window.
+    fn calls_in_window(who: &T::AccountId, window: T::BlockNumber) -> Vec<T::BlockNumber> {
+        let now = <system::Module<T>>::block_number();
+        Self::recent_calls(who)
+            .into_iter()
+            .filter(|block| block.saturating_add(window) > now)
+            .collect()
+    }
+
+    /// Record demand or offer of account, fails when rate limit is exceeded.
+    fn check_rate_limit(who: &T::AccountId) -> Result {
+        let (limit, window) = Self::current_rate_limit();
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let mut calls = Self::calls_in_window(who, window);
+        ensure!(calls.len() < limit as usize, "rate limit exceeded");
+
+        let now = <system::Module<T>>::block_number();
+        calls.push(now);
+        <RecentCalls<T>>::insert(who, calls);
+        Self::schedule_prune(who, now.saturating_add(window));
+        Ok(())
+    }
+
+    fn schedule_prune(who: &T::AccountId, at: T::BlockNumber) {
+        <ExpiringCalls<T>>::mutate(at, |v| if !v.contains(who) { v.push(who.clone()) });
+    }
+
+    /// Drop calls that left rate limit window, accounts without recent calls are removed.
+    fn prune_recent_calls(now: T::BlockNumber) {
+        let (_, window) = Self::current_rate_limit();
+        for who in <ExpiringCalls<T>>::take(now) {
+            let calls = Self::calls_in_window(&who, window);
+            match calls.first() {
+                None => <RecentCalls<T>>::remove(&who),
+                Some(oldest) => {
+                    // Rate limit window could be changed by governance
+                    Self::schedule_prune(&who, oldest.saturating_add(window).max(now.saturating_add(One::one())));
+                    <RecentCalls<T>>::insert(&who, calls);
+                },
+            }
+        }
+    }
+
     fn create_liability(
         demand: Demand<BalanceOf<T>,T::AccountId>,
-        offer: Offer<BalanceOf<T>,T::AccountId>
+        offer: OfferFor<T>
     ) -> Result {
         let Demand { order, sender: promisee } = demand;
-        let Offer { order: _o, sender: promisor } = offer;
+        let Offer { sender: promisor, .. } = offer;
         let index = Self::liability_count();
 
 		T::Currency::reserve(&promisee, order.cost)
             .map_err(|_| "promisee's balance too low")?;
 
+        <LiabilitiesOf<T>>::mutate(&promisee, |v| v.push(index));
+        if promisor != promisee {
+            <LiabilitiesOf<T>>::mutate(&promisor, |v| v.push(index));
+        }
+
         let liability = Liability { order, promisee, promisor, result: None };
         Self::deposit_event(RawEvent::NewLiability(index, liability.clone()));
         <LiabilityOf<T>>::insert(index, liability);
@@ -215,4 +509,558 @@ impl<T: Trait> Module<T> {
 
         Ok(())
     }
+
+    /// Match pending demands of order with offer while its capacity is available, at most
+    /// `limit` demands are processed. Returns `true` when offer could be matched further
+    /// and count of processed demands.
+    fn match_demands(order_hash: T::Hash, offer: &mut OfferFor<T>, limit: u32) -> (bool, u32) {
+        let mut demands = <DemandOf<T>>::get(order_hash);
+        let mut is_open = true;
+        let mut processed = 0;
+        while is_open && processed < limit {
+            let demand = match demands.pop() {
+                Some(demand) => demand,
+                None => break,
+            };
+            processed += 1;
+            if Self::create_liability(demand.clone(), offer.clone()).is_err() {
+                // Promisee can't pay liability cost, drop demand so it don't block the queue
+                Self::deposit_event(RawEvent::DemandClosed(order_hash.clone(), demand));
+                continue;
+            }
+
+            match offer.capacity.consume() {
+                Some(capacity) => offer.capacity = capacity,
+                None => is_open = false,
+            }
+        }
+        if demands.is_empty() {
+            <DemandOf<T>>::remove(order_hash);
+        } else {
+            <DemandOf<T>>::insert(order_hash, demands);
+        }
+        (is_open, processed)
+    }
+
+    /// Match demands left by offers that reached `MaxOfferMatches` with the latest offer
+    /// of their order, at most `MaxOfferMatches` demands are processed per block.
+    fn match_pending_demands() {
+        let mut pending = Self::pending_matches();
+        if pending.is_empty() {
+            return
+        }
+
+        let mut limit = T::MaxOfferMatches::get();
+        while let Some(order_hash) = pending.first().cloned() {
+            Self::remove_expired_offers(order_hash);
+            if let Some(mut offer) = <OfferOf<T>>::get(order_hash).last().cloned() {
+                if limit == 0 {
+                    break
+                }
+                let (is_open, processed) = Self::match_demands(order_hash, &mut offer, limit);
+                limit = limit.saturating_sub(processed);
+                <OfferOf<T>>::mutate(order_hash, |v| {
+                    v.pop();
+                    if is_open {
+                        v.push(offer.clone());
+                    }
+                });
+                let model = offer.order.model.clone();
+                if !is_open {
+                    Self::deposit_event(RawEvent::OfferClosed(order_hash.clone(), offer));
+                }
+                Self::update_orders_of(&model, order_hash);
+            }
+
+            if <DemandOf<T>>::get(order_hash).is_empty() || <OfferOf<T>>::get(order_hash).is_empty() {
+                pending.remove(0);
+            }
+        }
+
+        if pending.is_empty() {
+            <PendingMatches<T>>::kill();
+        } else {
+            <PendingMatches<T>>::put(pending);
+        }
+    }
+
+    /// Convert offers of order stored in legacy encoding, legacy offer is matched once
+    /// and never expires.
+    fn migrate_offers(order_hash: T::Hash) {
+        let key = BlakeTwo256::hash(&[&b"Robonomics OfferOf"[..], &order_hash.encode()[..]].concat());
+        let raw = match runtime_io::storage(key.as_ref()) {
+            Some(raw) => raw,
+            None => return,
+        };
+
+        let input = &mut &raw[..];
+        if <Vec<OfferFor<T>>>::decode(input).is_ok() && input.is_empty() {
+            return
+        }
+
+        let input = &mut &raw[..];
+        match <Vec<LegacyOffer<BalanceOf<T>,T::AccountId>>>::decode(input) {
+            Ok(legacy) if input.is_empty() => {
+                let offers: Vec<OfferFor<T>> = legacy.into_iter()
+                    .map(|LegacyOffer { order, sender }| Offer {
+                        order,
+                        sender,
+                        capacity: Capacity::Limited(1),
+                        expires: None,
+                    })
+                    .collect();
+                <OfferOf<T>>::insert(order_hash, offers);
+            },
+            // Value could not be decoded at all, drop it instead of failing on every access
+            _ => <OfferOf<T>>::remove(order_hash),
+        }
+    }
+
+    /// Keep order hash in model index while it has demands or offers waiting for matching.
+    fn update_orders_of(model: &Vec<u8>, order_hash: T::Hash) {
+        let is_open = !<DemandOf<T>>::get(order_hash).is_empty()
+            || !<OfferOf<T>>::get(order_hash).is_empty();
+        <OrdersOf<T>>::mutate(model, |hashes| {
+            let position = hashes.iter().position(|h| *h == order_hash);
+            match (is_open, position) {
+                (true, None) => hashes.push(order_hash),
+                (false, Some(i)) => { hashes.swap_remove(i); },
+                _ => (),
+            }
+        });
+    }
+
+    /// Demands and offers of given model waiting for matching, expired offers are skipped.
+    pub fn open_orders(model: Vec<u8>) -> (Vec<Demand<BalanceOf<T>,T::AccountId>>, Vec<OfferFor<T>>) {
+        let now = <system::Module<T>>::block_number();
+        let mut demands = Vec::new();
+        let mut offers = Vec::new();
+        for order_hash in Self::orders_of(model) {
+            demands.extend(<DemandOf<T>>::get(order_hash));
+            offers.extend(<OfferOf<T>>::get(order_hash).into_iter().filter(|o| !o.is_expired(now)));
+        }
+        (demands, offers)
+    }
+
+    /// Liabilities and orders waiting for matching at current block.
+    pub fn snapshot() -> Snapshot<BalanceOf<T>,T::AccountId,T::BlockNumber,T::Hash> {
+        let liability_count = Self::liability_count();
+        let liabilities = (0..liability_count)
+            .filter_map(|index| Self::liability_of(index).map(|l| (index, l)))
+            .collect();
+        let mut demands = Vec::new();
+        let mut offers = Vec::new();
+        for (_, hashes) in <OrdersOf<T>>::enumerate() {
+            for order_hash in hashes {
+                let order_demands = <DemandOf<T>>::get(order_hash);
+                if !order_demands.is_empty() {
+                    demands.push((order_hash, order_demands));
+                }
+                let order_offers = <OfferOf<T>>::get(order_hash);
+                if !order_offers.is_empty() {
+                    offers.push((order_hash, order_offers));
+                }
+            }
+        }
+        Snapshot { liability_count, liabilities, demands, offers }
+    }
+
+    /// Count of demands and offers waiting for matching.
+    pub fn queue_depth() -> (u64, u64) {
+        let mut demands = 0;
+        let mut offers = 0;
+        for (_, hashes) in <OrdersOf<T>>::enumerate() {
+            for order_hash in hashes {
+                demands += <DemandOf<T>>::get(order_hash).len() as u64;
+                offers += <OfferOf<T>>::get(order_hash).len() as u64;
+            }
+        }
+        (demands, offers)
+    }
+
+    /// Drop offers that could not be matched anymore.
+    fn remove_expired_offers(order_hash: T::Hash) {
+        let now = <system::Module<T>>::block_number();
+        let (expired, actual) = <OfferOf<T>>::get(order_hash)
+            .into_iter()
+            .partition::<Vec<_>, _>(|offer| offer.is_expired(now));
+
+        if expired.is_empty() {
+            return
+        }
+
+        for offer in expired {
+            Self::deposit_event(RawEvent::OfferClosed(order_hash.clone(), offer));
+        }
+        if actual.is_empty() {
+            <OfferOf<T>>::remove(order_hash);
+        } else {
+            <OfferOf<T>>::insert(order_hash, actual);
+        }
+    }
+
+    /// Decrease capacity of the latest offer, remove it when exhausted.
+    fn consume_offer(order_hash: T::Hash, offer: OfferFor<T>) {
+        <OfferOf<T>>::mutate(order_hash, |v| {
+            v.pop();
+            match offer.capacity.consume() {
+                Some(capacity) => v.push(Offer { capacity, .. offer }),
+                None => Self::deposit_event(RawEvent::OfferClosed(order_hash.clone(), offer)),
+            }
+        });
+    }
+}
+
+/// Innermost call of (possibly nested) call wrappers and account it is dispatched on behalf of.
+fn unwrap_call<'a, T: Trait>(
+    who: &T::AccountId,
+    call: &'a <T as system::Trait>::Call,
+) -> (T::AccountId, &'a <T as system::Trait>::Call) {
+    let mut unwrapped = (who.clone(), call);
+    while let Some(inner) = T::WrappedCalls::unwrap_call(&unwrapped.0, unwrapped.1) {
+        unwrapped = inner;
+    }
+    unwrapped
+}
+
+/// Reject demands and offers of accounts that exceeded rate limit before they get into
+/// transaction pool, the limit itself is checked on dispatch.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckRateLimit<T: Trait + Send + Sync>(PhantomData<T>);
+
+impl<T: Trait + Send + Sync> CheckRateLimit<T> {
+    /// utility constructor. Used only in client/factory code.
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn is_limited(call: &<T as system::Trait>::Call) -> bool {
+        match call.is_aux_sub_type() {
+            Some(Call::demand(..)) | Some(Call::offer(..)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trait + Send + Sync> rstd::fmt::Debug for CheckRateLimit<T> {
+    fn fmt(&self, f: &mut rstd::fmt::Formatter) -> rstd::fmt::Result {
+        write!(f, "CheckRateLimit")
+    }
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckRateLimit<T> where
+    <T as system::Trait>::Call: IsSubType<Module<T>, T>
+{
+    type AccountId = T::AccountId;
+    type Call = <T as system::Trait>::Call;
+    type AdditionalSigned = ();
+    type Pre = ();
+    fn additional_signed(&self) -> rstd::result::Result<(), TransactionValidityError> { Ok(()) }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        _info: DispatchInfo,
+        _len: usize,
+    ) -> TransactionValidity {
+        let (who, call) = unwrap_call::<T>(who, call);
+        if Self::is_limited(call) {
+            let (limit, window) = <Module<T>>::current_rate_limit();
+            if limit > 0 && <Module<T>>::calls_in_window(&who, window).len() >= limit as usize {
+                return InvalidTransaction::Custom(RATE_LIMIT_EXCEEDED).into();
+            }
+        }
+
+        Ok(ValidTransaction::default())
+    }
+}
+
+/// Reserve operational robonomics calls (e.g. `finalize`) for liability parties, so nobody
+/// else could fill operational part of block weight with them. Wrapped calls (e.g. proxy)
+/// are checked against account they are dispatched on behalf of.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckLiabilityParty<T: Trait + Send + Sync>(PhantomData<T>);
+
+impl<T: Trait + Send + Sync> CheckLiabilityParty<T> {
+    /// utility constructor. Used only in client/factory code.
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trait + Send + Sync> rstd::fmt::Debug for CheckLiabilityParty<T> {
+    fn fmt(&self, f: &mut rstd::fmt::Formatter) -> rstd::fmt::Result {
+        write!(f, "CheckLiabilityParty")
+    }
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckLiabilityParty<T> where
+    <T as system::Trait>::Call: IsSubType<Module<T>, T>
+{
+    type AccountId = T::AccountId;
+    type Call = <T as system::Trait>::Call;
+    type AdditionalSigned = ();
+    type Pre = ();
+    fn additional_signed(&self) -> rstd::result::Result<(), TransactionValidityError> { Ok(()) }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        _info: DispatchInfo,
+        _len: usize,
+    ) -> TransactionValidity {
+        let (who, call) = unwrap_call::<T>(who, call);
+        if let Some(Call::finalize(index, _)) = call.is_aux_sub_type() {
+            let is_party = <Module<T>>::liability_of(index)
+                .map(|liability| liability.is_finalizer(&who))
+                .unwrap_or(false);
+            if !is_party {
+                return InvalidTransaction::Custom(NOT_LIABILITY_PARTY).into();
+            }
+        }
+
+        Ok(ValidTransaction::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use primitives::H256;
+    use support::{assert_ok, assert_noop};
+    use sr_primitives::traits::OnInitialize;
+    use crate::{proxy, mock::*};
+
+    fn order(cost: u64) -> Order<u64, u64> {
+        Order { model: b"model".to_vec(), objective: b"objective".to_vec(), cost, custodian: None }
+    }
+
+    fn order_hash(cost: u64) -> H256 {
+        <Test as system::Trait>::Hashing::hash_of(&order(cost))
+    }
+
+    fn demand(who: u64, cost: u64) -> Result {
+        Robonomics::demand(Origin::signed(who), b"model".to_vec(), b"objective".to_vec(), cost, None)
+    }
+
+    fn offer(who: u64, cost: u64, capacity: Capacity, validity: Option<u64>) -> Result {
+        Robonomics::offer(Origin::signed(who), b"model".to_vec(), b"objective".to_vec(), cost, None, capacity, validity)
+    }
+
+    #[test]
+    fn offer_matches_limited_count_of_demands() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 10));
+            assert_ok!(demand(2, 10));
+            assert_ok!(demand(3, 10));
+            assert_ok!(offer(3, 10, Capacity::Unlimited, None));
+
+            assert_eq!(Robonomics::liability_count(), 2);
+            assert_eq!(Robonomics::demand_of(order_hash(10)).len(), 1);
+            assert_eq!(Robonomics::offer_of(order_hash(10)).len(), 1);
+        });
+    }
+
+    #[test]
+    fn leftover_demands_are_matched_on_next_blocks() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 10));
+            assert_ok!(demand(2, 10));
+            assert_ok!(demand(3, 10));
+            assert_ok!(offer(3, 10, Capacity::Unlimited, None));
+            assert_eq!(Robonomics::liability_count(), 2);
+            assert_eq!(Robonomics::pending_matches(), vec![order_hash(10)]);
+
+            Robonomics::on_initialize(1);
+            assert_eq!(Robonomics::liability_count(), 3);
+            assert!(Robonomics::demand_of(order_hash(10)).is_empty());
+            assert_eq!(Robonomics::offer_of(order_hash(10)).len(), 1);
+            assert!(Robonomics::pending_matches().is_empty());
+        });
+    }
+
+    #[test]
+    fn legacy_offer_is_migrated_on_access() {
+        with_externalities(&mut new_test_ext(), || {
+            let legacy = vec![LegacyOffer { order: order(10), sender: 2u64 }];
+            let key = BlakeTwo256::hash(&[&b"Robonomics OfferOf"[..], &order_hash(10).encode()[..]].concat());
+            runtime_io::set_storage(key.as_ref(), &legacy.encode());
+
+            assert_ok!(demand(1, 10));
+            assert_eq!(Robonomics::liability_of(0).unwrap().promisor, 2);
+            assert!(Robonomics::offer_of(order_hash(10)).is_empty());
+        });
+    }
+
+    #[test]
+    fn unfunded_demand_is_dropped_by_offer() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 10));
+            assert_ok!(demand(4, 10));
+            assert_ok!(offer(2, 10, Capacity::Limited(1), None));
+
+            assert_eq!(Robonomics::liability_count(), 1);
+            assert_eq!(Robonomics::liability_of(0).unwrap().promisee, 1);
+            assert!(Robonomics::demand_of(order_hash(10)).is_empty());
+            assert!(Robonomics::offer_of(order_hash(10)).is_empty());
+            assert!(Robonomics::orders_of(b"model".to_vec()).is_empty());
+        });
+    }
+
+    #[test]
+    fn offer_expiry_saturates() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(u64::max_value() - 1);
+            assert_ok!(offer(1, 10, Capacity::Unlimited, Some(10)));
+            assert_eq!(Robonomics::offer_of(order_hash(10))[0].expires, Some(u64::max_value()));
+        });
+    }
+
+    #[test]
+    fn queue_depth_counts_waiting_orders() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 10));
+            assert_ok!(demand(2, 20));
+            assert_ok!(offer(3, 30, Capacity::Unlimited, None));
+            assert_eq!(Robonomics::queue_depth(), (2, 1));
+
+            assert_ok!(offer(3, 10, Capacity::Limited(1), None));
+            assert_eq!(Robonomics::queue_depth(), (1, 1));
+        });
+    }
+
+    #[test]
+    fn finalize_pays_promisor_and_treasury() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 100));
+            assert_ok!(offer(2, 100, Capacity::Limited(1), None));
+            assert_eq!(Balances::reserved_balance(&1), 100);
+
+            assert_ok!(Robonomics::finalize(Origin::signed(2), 0, b"result".to_vec()));
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert_eq!(Balances::free_balance(&1), 900);
+            assert_eq!(Balances::free_balance(&2), 1090);
+            assert_eq!(Balances::free_balance(&TREASURY), 10);
+            assert_eq!(Robonomics::liability_of(0).unwrap().result, Some(b"result".to_vec()));
+
+            assert_noop!(
+                Robonomics::finalize(Origin::signed(2), 0, b"result".to_vec()),
+                "liability already finalized"
+            );
+        });
+    }
+
+    #[test]
+    fn finalize_without_reserved_cost_changes_nothing() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 100));
+            assert_ok!(offer(2, 100, Capacity::Limited(1), None));
+            Balances::unreserve(&1, 50);
+
+            assert_noop!(
+                Robonomics::finalize(Origin::signed(2), 0, b"result".to_vec()),
+                "promisee's reserved balance too low"
+            );
+            assert_eq!(Balances::reserved_balance(&1), 50);
+            assert_eq!(Balances::free_balance(&2), 1000);
+            assert_eq!(Balances::free_balance(&TREASURY), 0);
+            assert_eq!(Robonomics::liability_of(0).unwrap().result, None);
+        });
+    }
+
+    #[test]
+    fn finalize_only_by_liability_party() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 100));
+            assert_ok!(offer(2, 100, Capacity::Limited(1), None));
+            assert_noop!(
+                Robonomics::finalize(Origin::signed(3), 0, b"result".to_vec()),
+                "sender isn't liability custodian or promisor"
+            );
+        });
+    }
+
+    #[test]
+    fn wrapped_finalize_is_checked_against_real_account() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 100));
+            assert_ok!(offer(2, 100, Capacity::Limited(1), None));
+
+            let finalize = Box::new(OuterCall::Robonomics(Call::finalize(0, b"result".to_vec())));
+            let wrapped = OuterCall::Proxy(proxy::Call::proxy(2, finalize));
+            let validate = |who| CheckLiabilityParty::<Test>::new()
+                .validate(&who, &wrapped, DispatchInfo::default(), 0);
+
+            // Account 3 isn't proxy of promisor
+            assert_eq!(
+                validate(3),
+                Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(NOT_LIABILITY_PARTY)))
+            );
+
+            assert_ok!(Proxy::add_proxy(Origin::signed(2), 3));
+            assert!(validate(3).is_ok());
+        });
+    }
+
+    #[test]
+    fn wrapped_demand_is_rate_limited() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            assert_ok!(Robonomics::set_rate_limit(system::RawOrigin::Root.into(), 1, 10));
+            assert_ok!(demand(1, 10));
+            assert_ok!(Proxy::add_proxy(Origin::signed(1), 3));
+
+            let demand = Box::new(OuterCall::Robonomics(
+                Call::demand(b"model".to_vec(), b"objective".to_vec(), 20, None)
+            ));
+            let wrapped = OuterCall::Proxy(proxy::Call::proxy(1, demand));
+            assert_eq!(
+                CheckRateLimit::<Test>::new().validate(&3, &wrapped, DispatchInfo::default(), 0),
+                Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(RATE_LIMIT_EXCEEDED)))
+            );
+        });
+    }
+
+    #[test]
+    fn rate_limit_is_checked_on_dispatch() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            assert_ok!(Robonomics::set_rate_limit(system::RawOrigin::Root.into(), 2, 10));
+            assert_ok!(demand(1, 10));
+            assert_ok!(demand(1, 20));
+            assert_noop!(demand(1, 30), "rate limit exceeded");
+            assert_ok!(demand(2, 30));
+
+            System::set_block_number(11);
+            assert_ok!(demand(1, 30));
+        });
+    }
+
+    #[test]
+    fn recent_calls_are_pruned() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            assert_ok!(Robonomics::set_rate_limit(system::RawOrigin::Root.into(), 1, 5));
+            assert_ok!(demand(1, 10));
+            assert_eq!(Robonomics::recent_calls(1), vec![1]);
+
+            System::set_block_number(6);
+            Robonomics::on_initialize(6);
+            assert!(Robonomics::recent_calls(1).is_empty());
+        });
+    }
+
+    #[test]
+    fn disabled_rate_limit_records_nothing() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(demand(1, 10));
+            assert_ok!(demand(1, 20));
+            assert!(Robonomics::recent_calls(1).is_empty());
+        });
+    }
 }
@@ -0,0 +1,312 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Transaction fee sponsorship. Operator account (sponsor) pays fees of robonomics
+//! calls sent by robots it sponsors, so robots don't need own XRT balance.
+//!
+//! Robot accepts sponsorship by `accept_sponsor` call, fees of robot are charged from
+//! robot itself when sponsor allowance is exhausted. Transaction of robot that can't pay
+//! either is rejected with `SPONSOR_ALLOWANCE_EXHAUSTED` validity error.
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use system::ensure_signed;
+use support::{
+    StorageMap, decl_module, decl_storage, decl_event, ensure,
+    traits::{Currency, ExistenceRequirement, WithdrawReason, OnUnbalanced},
+    dispatch::Result,
+};
+use sr_primitives::{
+    weights::DispatchInfo,
+    traits::{SignedExtension, Convert, SaturatedConversion},
+    transaction_validity::{
+        TransactionPriority, TransactionValidity, ValidTransaction,
+        TransactionValidityError, InvalidTransaction,
+    },
+};
+use crate::constants::validity::SPONSOR_ALLOWANCE_EXHAUSTED;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+    <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Calls that could be paid by sponsor.
+pub trait IsSponsoredCall<Call> {
+    /// Returns `true` when call fees could be charged from sponsor.
+    fn is_sponsored(call: &Call) -> bool;
+}
+
+pub trait Trait: system::Trait {
+    /// Fee payment currency.
+    type Currency: Currency<Self::AccountId>;
+    /// Transaction fee calculator: encoded length, dispatch info and tip to final fee.
+    type ComputeFee: Convert<(usize, DispatchInfo, BalanceOf<Self>), BalanceOf<Self>>;
+    /// Handler for the collected transaction fees.
+    type TransactionPayment: OnUnbalanced<NegativeImbalanceOf<Self>>;
+    /// Calls that could be sponsored.
+    type SponsoredCalls: IsSponsoredCall<<Self as system::Trait>::Call>;
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin
+    {
+        fn deposit_event() = default;
+
+        /// Propose to pay fees of robot transactions until allowance is spent,
+        /// sponsorship starts when robot accepts it. Current sponsor of robot
+        /// changes allowance immediately.
+        pub fn sponsor(
+            origin,
+            robot: T::AccountId,
+            #[compact] allowance: BalanceOf<T>
+        ) -> Result {
+            let sponsor = ensure_signed(origin)?;
+
+            if Self::sponsor_of(&robot) == Some(sponsor.clone()) {
+                <Allowance<T>>::insert(&robot, allowance);
+                Self::deposit_event(RawEvent::Sponsored(sponsor, robot, allowance));
+            } else {
+                <ProposalOf<T>>::insert(&robot, (sponsor.clone(), allowance));
+                Self::deposit_event(RawEvent::Proposed(sponsor, robot, allowance));
+            }
+
+            Ok(())
+        }
+
+        /// Accept sponsorship proposed by given account, replaces current sponsor.
+        pub fn accept_sponsor(origin, sponsor: T::AccountId) -> Result {
+            let robot = ensure_signed(origin)?;
+            let (proposer, allowance) = Self::proposal_of(&robot).ok_or("no sponsorship proposal")?;
+            ensure!(proposer == sponsor, "sponsorship proposed by another account");
+
+            <ProposalOf<T>>::remove(&robot);
+            <SponsorOf<T>>::insert(&robot, &sponsor);
+            <Allowance<T>>::insert(&robot, allowance);
+            Self::deposit_event(RawEvent::Sponsored(sponsor, robot, allowance));
+
+            Ok(())
+        }
+
+        /// Stop paying fees of robot transactions.
+        pub fn revoke(origin, robot: T::AccountId) -> Result {
+            let sponsor = ensure_signed(origin)?;
+            ensure!(Self::sponsor_of(&robot) == Some(sponsor.clone()), "sender isn't robot sponsor");
+
+            <SponsorOf<T>>::remove(&robot);
+            <Allowance<T>>::remove(&robot);
+            Self::deposit_event(RawEvent::Revoked(sponsor, robot));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Sponsorship {
+        /// Get sponsor of robot account.
+        pub SponsorOf get(sponsor_of): map T::AccountId => Option<T::AccountId>;
+
+        /// Remaining fee allowance of robot account.
+        pub Allowance get(allowance): map T::AccountId => BalanceOf<T>;
+
+        /// Sponsor and allowance proposed to robot account and not accepted yet.
+        pub ProposalOf get(proposal_of): map T::AccountId => Option<(T::AccountId, BalanceOf<T>)>;
+    }
+}
+
+decl_event! {
+    pub enum Event<T>
+        where <T as system::Trait>::AccountId,
+              Balance = BalanceOf<T>
+    {
+        /// Sponsor proposed to pay robot fees with given allowance.
+        Proposed(AccountId, AccountId, Balance),
+
+        /// Sponsor pays robot fees with given allowance.
+        Sponsored(AccountId, AccountId, Balance),
+
+        /// Sponsorship revoked.
+        Revoked(AccountId, AccountId),
+    }
+}
+
+/// Charge transaction fees from sponsor for sponsored calls of robots and
+/// from transactor otherwise. Replacement for `balances::TakeFees`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct TakeSponsoredFees<T: Trait>(#[codec(compact)] BalanceOf<T>);
+
+impl<T: Trait> TakeSponsoredFees<T> {
+    /// utility constructor. Used only in client/factory code.
+    pub fn from(tip: BalanceOf<T>) -> Self {
+        Self(tip)
+    }
+
+    /// Tip paid on top of transaction fee.
+    pub fn tip(&self) -> BalanceOf<T> {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trait> rstd::fmt::Debug for TakeSponsoredFees<T> {
+    fn fmt(&self, f: &mut rstd::fmt::Formatter) -> rstd::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Trait> SignedExtension for TakeSponsoredFees<T> where
+    BalanceOf<T>: Send + Sync
+{
+    type AccountId = T::AccountId;
+    type Call = <T as system::Trait>::Call;
+    type AdditionalSigned = ();
+    type Pre = ();
+    fn additional_signed(&self) -> rstd::result::Result<(), TransactionValidityError> { Ok(()) }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: DispatchInfo,
+        len: usize,
+    ) -> TransactionValidity {
+        let fee = T::ComputeFee::convert((len, info, self.0));
+
+        let sponsor = if T::SponsoredCalls::is_sponsored(call) {
+            <Module<T>>::sponsor_of(who)
+        } else {
+            None
+        };
+
+        // Robot pays itself when sponsor allowance isn't enough
+        let (payer, is_exhausted) = match sponsor {
+            Some(sponsor) => {
+                let allowance = <Module<T>>::allowance(who);
+                if allowance < fee {
+                    (who.clone(), true)
+                } else {
+                    <Allowance<T>>::insert(who, allowance - fee);
+                    (sponsor, false)
+                }
+            },
+            None => (who.clone(), false),
+        };
+
+        let imbalance = match T::Currency::withdraw(
+            &payer,
+            fee,
+            WithdrawReason::TransactionPayment,
+            ExistenceRequirement::KeepAlive,
+        ) {
+            Ok(imbalance) => imbalance,
+            Err(_) if is_exhausted => return InvalidTransaction::Custom(SPONSOR_ALLOWANCE_EXHAUSTED).into(),
+            Err(_) => return InvalidTransaction::Payment.into(),
+        };
+        T::TransactionPayment::on_unbalanced(imbalance);
+
+        let mut r = ValidTransaction::default();
+        r.priority = fee.saturated_into::<TransactionPriority>();
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{assert_ok, assert_noop};
+    use crate::mock::*;
+
+    fn take_fee(who: u64) -> TransactionValidity {
+        let call = OuterCall::Balances(balances::Call::transfer(0, 0));
+        TakeSponsoredFees::<Test>::from(0).validate(&who, &call, DispatchInfo::default(), 0)
+    }
+
+    #[test]
+    fn sponsorship_starts_when_robot_accepts() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Sponsorship::sponsor(Origin::signed(1), 4, 100));
+            assert_eq!(Sponsorship::sponsor_of(4), None);
+            assert_eq!(Sponsorship::proposal_of(4), Some((1, 100)));
+
+            assert_noop!(Sponsorship::accept_sponsor(Origin::signed(4), 2), "sponsorship proposed by another account");
+            assert_ok!(Sponsorship::accept_sponsor(Origin::signed(4), 1));
+            assert_eq!(Sponsorship::sponsor_of(4), Some(1));
+            assert_eq!(Sponsorship::allowance(4), 100);
+            assert_eq!(Sponsorship::proposal_of(4), None);
+
+            // Current sponsor changes allowance without acceptance
+            assert_ok!(Sponsorship::sponsor(Origin::signed(1), 4, 50));
+            assert_eq!(Sponsorship::allowance(4), 50);
+        });
+    }
+
+    #[test]
+    fn unaccepted_sponsor_pays_nothing() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Sponsorship::sponsor(Origin::signed(1), 2, 100));
+            assert!(take_fee(2).is_ok());
+            assert_eq!(Balances::free_balance(&1), 1000);
+            assert_eq!(Balances::free_balance(&2), 1000 - FEE);
+        });
+    }
+
+    #[test]
+    fn sponsor_pays_fees_within_allowance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Sponsorship::sponsor(Origin::signed(1), 4, FEE + FEE / 2));
+            assert_ok!(Sponsorship::accept_sponsor(Origin::signed(4), 1));
+
+            assert!(take_fee(4).is_ok());
+            assert_eq!(Balances::free_balance(&1), 1000 - FEE);
+            assert_eq!(Sponsorship::allowance(4), FEE / 2);
+
+            // Allowance is too low and robot has no balance
+            assert_eq!(
+                take_fee(4),
+                Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(SPONSOR_ALLOWANCE_EXHAUSTED)))
+            );
+            assert_eq!(Balances::free_balance(&1), 1000 - FEE);
+        });
+    }
+
+    #[test]
+    fn robot_pays_when_allowance_is_exhausted() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Sponsorship::sponsor(Origin::signed(1), 2, FEE / 2));
+            assert_ok!(Sponsorship::accept_sponsor(Origin::signed(2), 1));
+
+            assert!(take_fee(2).is_ok());
+            assert_eq!(Balances::free_balance(&1), 1000);
+            assert_eq!(Balances::free_balance(&2), 1000 - FEE);
+            assert_eq!(Sponsorship::allowance(2), FEE / 2);
+        });
+    }
+
+    #[test]
+    fn only_sponsor_revokes() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Sponsorship::sponsor(Origin::signed(1), 2, 100));
+            assert_ok!(Sponsorship::accept_sponsor(Origin::signed(2), 1));
+            assert_noop!(Sponsorship::revoke(Origin::signed(3), 2), "sender isn't robot sponsor");
+            assert_ok!(Sponsorship::revoke(Origin::signed(1), 2));
+            assert_eq!(Sponsorship::sponsor_of(2), None);
+        });
+    }
+}
@@ -17,8 +17,10 @@
 ///////////////////////////////////////////////////////////////////////////////
 //! A set of primitive types used in substrate runtime.
 
+use rstd::vec::Vec;
+use crate::robonomics::{Liability, Demand, Offer, Snapshot};
 use sr_primitives::{
-    generic, traits::{Verify, BlakeTwo256}, OpaqueExtrinsic, AnySignature
+    generic, traits::{Verify, BlakeTwo256}, OpaqueExtrinsic, AnySignature,
 };
 
 /// Index of a block number in the chain.
@@ -52,6 +54,14 @@ pub type Hash = primitives::H256;
 /// time scale is milliseconds.
 pub type Timestamp = u64;
 
+/// Key types used by robonomics node.
+pub mod key_types {
+    use primitives::crypto::KeyTypeId;
+
+    /// ROS bridge account key, sr25519 keypair used to sign robonomics extrinsics.
+    pub const ROBO: KeyTypeId = KeyTypeId(*b"robo");
+}
+
 /// Digest item type.
 pub type DigestItem = generic::DigestItem<Hash>;
 
@@ -73,4 +83,28 @@ client::decl_runtime_apis! {
         /// Get current account nonce of given `AccountId`.
         fn account_nonce(account: AccountId) -> Index;
     }
+
+    /// The API to resolve account identity.
+    pub trait IdentityApi {
+        /// Get display name of given `AccountId` when its identity is verified.
+        fn display_name(account: AccountId) -> Option<Vec<u8>>;
+    }
+
+    /// The API to query robonomics liabilities and orders.
+    pub trait RobonomicsApi {
+        /// Get liability by index.
+        fn liability(index: u64) -> Option<Liability<Balance, AccountId>>;
+
+        /// Get liabilities where given account is promisee or promisor.
+        fn liabilities_of(account: AccountId) -> Vec<(u64, Liability<Balance, AccountId>)>;
+
+        /// Get demands and offers of given model waiting for matching.
+        fn open_orders(model: Vec<u8>) -> (Vec<Demand<Balance, AccountId>>, Vec<Offer<Balance, AccountId, BlockNumber>>);
+
+        /// Get all liabilities and orders waiting for matching.
+        fn snapshot() -> Snapshot<Balance, AccountId, BlockNumber, Hash>;
+
+        /// Get count of demands and offers waiting for matching.
+        fn queue_depth() -> (u64, u64);
+    }
 }
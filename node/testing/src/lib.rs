@@ -0,0 +1,334 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Local network of in-process robonomics nodes for end-to-end tests.
+//!
+//! Nodes are started from given chain spec with authority keys of given seeds
+//! and connected over localhost, `robo` key of the seed is inserted into keystore
+//! of each node to run ROS bridge. See `ros_master` for stubbed ROS master.
+
+use std::{
+    sync::Arc, thread,
+    time::{Duration, Instant},
+    sync::atomic::{AtomicU16, Ordering},
+};
+use log::error;
+use parking_lot::Mutex;
+use futures::{Future, future};
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+use codec::{Encode, Decode};
+use network::config::TransportConfig;
+use primitives::{
+    Pair, blake2_256, twox_128, sr25519,
+    storage::StorageKey,
+};
+use sr_primitives::{
+    generic::{BlockId, Era},
+    traits::ProvideRuntimeApi,
+};
+use substrate_service::{
+    AbstractService, Configuration, Roles,
+    error::Error as ServiceError,
+};
+use transaction_pool::txpool::ExtrinsicFor;
+use node_runtime::{
+    Call, Event, EventRecord, SignedExtra, UncheckedExtrinsic, RuntimeApi, GenesisConfig, VERSION,
+    robonomics::{self, CheckRateLimit, CheckLiabilityParty},
+    sponsorship::TakeSponsoredFees,
+    types::{Block, Hash, AccountId, Balance, BlockNumber, AccountNonceApi, key_types},
+};
+use substrate_ros_api::{FeeApi, FeeEstimate};
+use node_cli::chain_spec::ChainSpec;
+
+#[cfg(feature = "ros")]
+pub mod ros_master;
+
+/// First port of node listen addresses, every started node takes next one.
+const BASE_PORT: u16 = 30_500;
+
+/// Interval of condition checks while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static NEXT_PORT: AtomicU16 = AtomicU16::new(BASE_PORT);
+
+/// Robonomics event emitted at block.
+pub type RobonomicsEvent = robonomics::Event<node_runtime::Runtime>;
+
+/// Node of test network.
+pub struct TestNode<S> {
+    /// Authority seed of node, e.g. `Alice`
+    pub seed: String,
+    /// Account key of node seed
+    pub key: sr25519::Pair,
+    service: Arc<Mutex<S>>,
+}
+
+/// Local network of in-process nodes.
+pub struct TestNet<S> {
+    /// Nodes in order of given seeds
+    pub nodes: Vec<TestNode<S>>,
+    runtime: Option<Runtime>,
+    _base_path: TempDir,
+}
+
+impl<S> TestNet<S> where
+    S: AbstractService<Block = Block, RuntimeApi = RuntimeApi> + Send + 'static,
+{
+    /// Start node for each seed using `start` service builder, first node is boot node of others.
+    pub fn new<F>(spec: ChainSpec, seeds: &[&str], start: F) -> Result<Self, ServiceError> where
+        F: Fn(usize, Configuration<(), GenesisConfig>) -> Result<S, ServiceError>,
+    {
+        let base_path = TempDir::new()?;
+        let mut runtime = Runtime::new()?;
+        let mut nodes: Vec<TestNode<S>> = Vec::new();
+        let mut boot_nodes = Vec::new();
+
+        for (index, seed) in seeds.iter().enumerate() {
+            let port = NEXT_PORT.fetch_add(1, Ordering::SeqCst);
+            let config = node_config(&spec, seed, port, &base_path, boot_nodes.clone())?;
+
+            let service = start(index, config)?;
+            if index == 0 {
+                let peer_id = service.network().local_peer_id();
+                boot_nodes.push(format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", port, peer_id.to_base58()));
+            }
+
+            let service = Arc::new(Mutex::new(service));
+            let polled = service.clone();
+            runtime.spawn(future::poll_fn(move || polled.lock().poll())
+                .map_err(|e| error!("Test node failed: {:?}", e)));
+
+            nodes.push(TestNode {
+                seed: seed.to_string(),
+                key: seed_key(seed),
+                service,
+            });
+        }
+
+        Ok(TestNet { nodes, runtime: Some(runtime), _base_path: base_path })
+    }
+
+    /// Wait until condition is true, returns `false` on timeout.
+    pub fn wait_for<F: FnMut(&Self) -> bool>(&self, timeout: Duration, mut condition: F) -> bool {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if condition(self) {
+                return true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        false
+    }
+
+    /// Wait until all nodes import block with given number.
+    pub fn wait_for_blocks(&self, number: BlockNumber, timeout: Duration) -> bool {
+        self.wait_for(timeout, |net| net.nodes.iter().all(|node| node.best_number() >= number))
+    }
+
+    /// Wait for robonomics event on node starting from given block, returns
+    /// block number and first value returned by `matcher`.
+    pub fn wait_for_event<T, F>(
+        &self,
+        node: usize,
+        from: BlockNumber,
+        timeout: Duration,
+        mut matcher: F,
+    ) -> Option<(BlockNumber, T)> where
+        F: FnMut(&RobonomicsEvent) -> Option<T>,
+    {
+        let node = &self.nodes[node];
+        let mut next = from;
+        let mut found = None;
+        self.wait_for(timeout, |_| {
+            while next <= node.best_number() {
+                found = node.robonomics_events(next).iter()
+                    .find_map(|e| matcher(e))
+                    .map(|value| (next, value));
+                next += 1;
+                if found.is_some() {
+                    return true;
+                }
+            }
+            false
+        });
+        found
+    }
+}
+
+impl<S> Drop for TestNet<S> {
+    fn drop(&mut self) {
+        self.nodes.clear();
+        if let Some(runtime) = self.runtime.take() {
+            let _ = runtime.shutdown_now().wait();
+        }
+    }
+}
+
+impl<S> TestNode<S> where
+    S: AbstractService<Block = Block, RuntimeApi = RuntimeApi>,
+{
+    /// Account of node seed.
+    pub fn account(&self) -> AccountId {
+        self.key.public()
+    }
+
+    /// Best block number of node.
+    pub fn best_number(&self) -> BlockNumber {
+        self.service.lock().client().info().chain.best_number
+    }
+
+    fn best_hash(&self) -> Hash {
+        self.service.lock().client().info().chain.best_hash
+    }
+
+    /// Read and decode storage value at best block.
+    pub fn storage<T: Decode>(&self, key: &[u8]) -> Option<T> {
+        self.storage_at(BlockId::hash(self.best_hash()), key)
+    }
+
+    fn storage_at<T: Decode>(&self, block: BlockId<Block>, key: &[u8]) -> Option<T> {
+        self.service.lock().client()
+            .storage(&block, &StorageKey(key.to_vec()))
+            .expect("storage is available on full node")
+            .and_then(|data| T::decode(&mut &data.0[..]).ok())
+    }
+
+    /// Free balance of account at best block.
+    pub fn free_balance(&self, account: &AccountId) -> Balance {
+        let key = [&b"Balances FreeBalance"[..], &account.encode()[..]].concat();
+        self.storage(&blake2_256(&key)).unwrap_or(0)
+    }
+
+    /// Reserved balance of account at best block.
+    pub fn reserved_balance(&self, account: &AccountId) -> Balance {
+        let key = [&b"Balances ReservedBalance"[..], &account.encode()[..]].concat();
+        self.storage(&blake2_256(&key)).unwrap_or(0)
+    }
+
+    /// Robonomics events emitted at block with given number.
+    pub fn robonomics_events(&self, number: BlockNumber) -> Vec<RobonomicsEvent> {
+        let records: Vec<EventRecord<Event, Hash>> = self
+            .storage_at(BlockId::number(number), &twox_128(b"System Events"))
+            .unwrap_or_default();
+        records.into_iter()
+            .filter_map(|r| match r.event {
+                Event::robonomics(e) => Some(e),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Sign call by node account key.
+    pub fn sign(&self, call: Call) -> UncheckedExtrinsic {
+        let service = self.service.lock();
+        let client = service.client();
+        let info = client.info().chain;
+        let nonce = client.runtime_api()
+            .account_nonce(&BlockId::hash(info.best_hash), self.account())
+            .expect("account nonce is available");
+
+        let extra: SignedExtra = (
+            system::CheckVersion::new(),
+            system::CheckGenesis::new(),
+            system::CheckEra::from(Era::Immortal),
+            system::CheckNonce::from(nonce),
+            system::CheckWeight::new(),
+            TakeSponsoredFees::from(0),
+            CheckRateLimit::new(),
+            CheckLiabilityParty::new(),
+        );
+        let additional = (VERSION.spec_version, info.genesis_hash, info.genesis_hash, (), (), (), (), ());
+        let raw_payload = (call, extra, additional);
+        let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
+            self.key.sign(&blake2_256(payload)[..])
+        } else {
+            self.key.sign(payload)
+        });
+        let (call, extra, _) = raw_payload;
+
+        UncheckedExtrinsic::new_signed(call, self.account().into(), signature.into(), extra)
+    }
+
+    /// Estimate fee of extrinsic at best block.
+    pub fn estimate_fee(&self, extrinsic: &UncheckedExtrinsic) -> FeeEstimate<Balance> {
+        let xt = Decode::decode(&mut extrinsic.encode().as_slice()).expect("extrinsic is opaque");
+        self.service.lock().client().runtime_api()
+            .estimate_fee(&BlockId::hash(self.best_hash()), xt)
+            .expect("fee estimate is available")
+    }
+
+    /// Submit extrinsic into node transaction pool.
+    pub fn submit(&self, extrinsic: UncheckedExtrinsic) -> Result<(), String> {
+        let block = BlockId::hash(self.best_hash());
+        let xt: ExtrinsicFor<S::TransactionPoolApi> = Decode::decode(&mut extrinsic.encode().as_slice())
+            .map_err(|e| format!("{:?}", e))?;
+        self.service.lock().transaction_pool()
+            .submit_one(&block, xt)
+            .map(|_| ())
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Sign call by node account key and submit it.
+    pub fn submit_call(&self, call: Call) -> Result<(), String> {
+        self.submit(self.sign(call))
+    }
+}
+
+/// Account key derived from seed, e.g. `Alice` is `//Alice`.
+pub fn seed_key(seed: &str) -> sr25519::Pair {
+    sr25519::Pair::from_string(&format!("//{}", seed), None).expect("static values are valid; qed")
+}
+
+/// Configuration of authority node listening given localhost port.
+fn node_config(
+    spec: &ChainSpec,
+    seed: &str,
+    port: u16,
+    base_path: &TempDir,
+    boot_nodes: Vec<String>,
+) -> Result<Configuration<(), GenesisConfig>, ServiceError> {
+    let root = base_path.path().join(seed);
+    let keystore_path = root.join("keystore");
+    let network_path = root.join("network").to_string_lossy().into_owned();
+
+    // ROS bridge account
+    keystore::Store::open(keystore_path.clone(), None)?
+        .write()
+        .insert_by_type::<sr25519::Pair>(key_types::ROBO, &format!("//{}", seed))?;
+
+    let mut config = Configuration::default_with_spec(spec.clone());
+    config.name = format!("{} test node", seed);
+    config.impl_name = "robonomics-test-node";
+    config.impl_version = "0.1";
+    config.roles = Roles::AUTHORITY;
+    config.dev_key_seed = Some(format!("//{}", seed));
+    config.keystore_path = Some(keystore_path);
+    config.database_path = root.join("db");
+    config.network.config_path = Some(network_path.clone());
+    config.network.net_config_path = Some(network_path);
+    config.network.listen_addresses = vec![
+        format!("/ip4/127.0.0.1/tcp/{}", port).parse().expect("static values are valid; qed")
+    ];
+    config.network.boot_nodes = boot_nodes;
+    config.network.transport = TransportConfig::Normal {
+        enable_mdns: false,
+        wasm_external_transport: None,
+    };
+
+    Ok(config)
+}
@@ -0,0 +1,255 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Stubbed ROS master for tests of ROS bridge.
+//!
+//! Implements subset of ROS master XML-RPC API required by rosrust nodes:
+//! topic and service registration with publisher updates, node and service lookup.
+//! http://wiki.ros.org/ROS/Master_API
+
+use std::{
+    env, thread,
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::Arc,
+};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use futures::{Future, Stream, sync::oneshot};
+use hyper::{Body, Client, Request, Response, Server, service::service_fn};
+
+/// Registrations known by master.
+#[derive(Default)]
+struct State {
+    /// XML-RPC URI of node by name
+    nodes: HashMap<String, String>,
+    /// Node URIs by topic name
+    publishers: HashMap<String, HashSet<String>>,
+    subscribers: HashMap<String, HashSet<String>>,
+    /// Service URI by service name
+    services: HashMap<String, String>,
+}
+
+/// Running stub master, it's stopped on drop.
+pub struct StubMaster {
+    uri: String,
+    state: Arc<Mutex<State>>,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl StubMaster {
+    /// Start master on given localhost port and point `ROS_MASTER_URI` to it.
+    pub fn start(port: u16) -> Self {
+        let uri = format!("http://127.0.0.1:{}/", port);
+        let state = Arc::new(Mutex::new(State::default()));
+        let (stop, stopped) = oneshot::channel();
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let master_uri = uri.clone();
+        let server_state = state.clone();
+        let server = Server::bind(&addr)
+            .serve(move || {
+                let uri = master_uri.clone();
+                let state = server_state.clone();
+                service_fn(move |req: Request<Body>| {
+                    let uri = uri.clone();
+                    let state = state.clone();
+                    req.into_body().concat2().map(move |body| {
+                        let body = String::from_utf8_lossy(&body).into_owned();
+                        Response::new(Body::from(handle(&uri, &state, &body)))
+                    })
+                })
+            })
+            .with_graceful_shutdown(stopped)
+            .map_err(|e| warn!("ROS master stub error: {}", e));
+        thread::spawn(move || hyper::rt::run(server));
+
+        env::set_var("ROS_MASTER_URI", &uri);
+        env::set_var("ROS_HOSTNAME", "127.0.0.1");
+        StubMaster { uri, state, stop: Some(stop) }
+    }
+
+    /// Master XML-RPC URI.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Topics with registered publishers.
+    pub fn published_topics(&self) -> Vec<String> {
+        self.state.lock().publishers.keys().cloned().collect()
+    }
+
+    /// Topics with registered subscribers.
+    pub fn subscribed_topics(&self) -> Vec<String> {
+        self.state.lock().subscribers.keys().cloned().collect()
+    }
+
+    /// Registered services.
+    pub fn services(&self) -> Vec<String> {
+        self.state.lock().services.keys().cloned().collect()
+    }
+}
+
+impl Drop for StubMaster {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+/// XML-RPC value of master response.
+enum Value {
+    Int(i32),
+    Bool(bool),
+    Str(String),
+    Array(Vec<String>),
+}
+
+impl Value {
+    fn to_xml(&self) -> String {
+        match self {
+            Value::Int(v) => format!("<value><i4>{}</i4></value>", v),
+            Value::Bool(v) => format!("<value><boolean>{}</boolean></value>", *v as u8),
+            Value::Str(v) => format!("<value><string>{}</string></value>", v),
+            Value::Array(v) => format!(
+                "<value><array><data>{}</data></array></value>",
+                v.iter().map(|s| Value::Str(s.clone()).to_xml()).collect::<String>(),
+            ),
+        }
+    }
+}
+
+fn response(code: i32, status: &str, value: Value) -> String {
+    format!(
+        "<?xml version=\"1.0\"?><methodResponse><params><param><value><array><data>{}{}{}</data></array></value></param></params></methodResponse>",
+        Value::Int(code).to_xml(), Value::Str(status.into()).to_xml(), value.to_xml(),
+    )
+}
+
+fn call(method: &str, params: &[Value]) -> String {
+    format!(
+        "<?xml version=\"1.0\"?><methodCall><methodName>{}</methodName><params>{}</params></methodCall>",
+        method, params.iter().map(|p| format!("<param>{}</param>", p.to_xml())).collect::<String>(),
+    )
+}
+
+/// Text between tags of every occurrence of `tag` in `xml`.
+fn tag_contents<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut contents = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                contents.push(&rest[..end]);
+                rest = &rest[end + close.len()..];
+            },
+            None => break,
+        }
+    }
+    contents
+}
+
+/// Method name and string parameters of XML-RPC call.
+fn parse_call(body: &str) -> (String, Vec<String>) {
+    let method = tag_contents(body, "methodName").first().map(|m| m.trim().to_string()).unwrap_or_default();
+    let params = tag_contents(body, "param").iter()
+        .map(|param| {
+            let value = tag_contents(param, "value").first().cloned().unwrap_or_default();
+            tag_contents(value, "string").first().cloned().unwrap_or(value).trim().to_string()
+        })
+        .collect();
+    (method, params)
+}
+
+/// Notify subscriber node about actual topic publishers.
+fn publisher_update(node_uri: String, topic: String, publishers: Vec<String>) {
+    let body = call("publisherUpdate", &[
+        Value::Str("/master".into()),
+        Value::Str(topic),
+        Value::Array(publishers),
+    ]);
+    let request = Request::post(node_uri.as_str())
+        .header("Content-Type", "text/xml")
+        .body(Body::from(body))
+        .expect("static values are valid; qed");
+    hyper::rt::spawn(Client::new().request(request)
+        .map(|_| ())
+        .map_err(move |e| warn!("Unable to update publishers of {}: {}", node_uri, e)));
+}
+
+fn handle(master_uri: &str, state: &Mutex<State>, body: &str) -> String {
+    let (method, params) = parse_call(body);
+    debug!("ROS master stub call {}{:?}", method, params);
+    let param = |i: usize| params.get(i).cloned().unwrap_or_default();
+    let mut state = state.lock();
+
+    match method.as_str() {
+        "registerPublisher" => {
+            let (caller, topic, uri) = (param(0), param(1), param(3));
+            state.nodes.insert(caller, uri.clone());
+            state.publishers.entry(topic.clone()).or_default().insert(uri);
+            let publishers: Vec<_> = state.publishers[&topic].iter().cloned().collect();
+            let subscribers: Vec<_> = state.subscribers.get(&topic).into_iter().flatten().cloned().collect();
+            for subscriber in subscribers.iter() {
+                publisher_update(subscriber.clone(), topic.clone(), publishers.clone());
+            }
+            response(1, "", Value::Array(subscribers))
+        },
+        "unregisterPublisher" => {
+            let (topic, uri) = (param(1), param(2));
+            let removed = state.publishers.get_mut(&topic).map(|p| p.remove(&uri)).unwrap_or(false);
+            response(1, "", Value::Int(removed as i32))
+        },
+        "registerSubscriber" => {
+            let (caller, topic, uri) = (param(0), param(1), param(3));
+            state.nodes.insert(caller, uri.clone());
+            state.subscribers.entry(topic.clone()).or_default().insert(uri);
+            let publishers = state.publishers.get(&topic).into_iter().flatten().cloned().collect();
+            response(1, "", Value::Array(publishers))
+        },
+        "unregisterSubscriber" => {
+            let (topic, uri) = (param(1), param(2));
+            let removed = state.subscribers.get_mut(&topic).map(|s| s.remove(&uri)).unwrap_or(false);
+            response(1, "", Value::Int(removed as i32))
+        },
+        "registerService" => {
+            let (caller, service, service_uri, uri) = (param(0), param(1), param(2), param(3));
+            state.nodes.insert(caller, uri);
+            state.services.insert(service, service_uri);
+            response(1, "", Value::Int(0))
+        },
+        "unregisterService" => {
+            let removed = state.services.remove(&param(1)).is_some();
+            response(1, "", Value::Int(removed as i32))
+        },
+        "lookupService" => match state.services.get(&param(1)) {
+            Some(uri) => response(1, "", Value::Str(uri.clone())),
+            None => response(-1, "unknown service", Value::Str(String::new())),
+        },
+        "lookupNode" => match state.nodes.get(&param(1)) {
+            Some(uri) => response(1, "", Value::Str(uri.clone())),
+            None => response(-1, "unknown node", Value::Str(String::new())),
+        },
+        "getUri" => response(1, "", Value::Str(master_uri.into())),
+        "getPid" => response(1, "", Value::Int(std::process::id() as i32)),
+        "hasParam" => response(1, "", Value::Bool(false)),
+        _ => response(-1, "not supported by stub master", Value::Int(0)),
+    }
+}
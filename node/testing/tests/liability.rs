@@ -0,0 +1,89 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Liability flow of local testnet: demand and offer are matched, then liability is finalized.
+
+use std::time::Duration;
+use node_cli::{RunParams, service, chain_spec::local_testnet_config};
+use node_runtime::{
+    Call, RobonomicsCall,
+    robonomics::{RawEvent, Capacity},
+    constants::currency::XRT,
+};
+use node_testing::TestNet;
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+#[test]
+fn liability_is_created_and_finalized() {
+    let net = TestNet::new(local_testnet_config(), &["Alice", "Bob"], |_, config|
+        service::new_full(config, RunParams::default())
+    ).expect("test network started");
+    assert!(net.wait_for_blocks(2, TIMEOUT), "blocks aren't produced");
+
+    let (alice, bob) = (&net.nodes[0], &net.nodes[1]);
+    let model = b"QmWboFP8XeBtFMbNYK3Ne8Z3gKFBSR5iQzkKgeNgQz3dz4".to_vec();
+    let objective = b"QmaCPDxAMMLqA4cGpVRtPfGwv7yqr9sM3URD8GbwgS4mTF".to_vec();
+    let cost = 10 * XRT;
+    let alice_balance = alice.free_balance(&alice.account());
+    let from = alice.best_number();
+
+    alice.submit_call(Call::Robonomics(RobonomicsCall::demand(
+        model.clone(), objective.clone(), cost, None,
+    ))).expect("demand submitted");
+    bob.submit_call(Call::Robonomics(RobonomicsCall::offer(
+        model, objective, cost, None, Capacity::Limited(1), None,
+    ))).expect("offer submitted");
+
+    let (_, (index, liability)) = net.wait_for_event(0, from, TIMEOUT, |e| match e {
+        RawEvent::NewLiability(index, liability) => Some((*index, liability.clone())),
+        _ => None,
+    }).expect("liability created");
+    assert_eq!(liability.promisee, alice.account());
+    assert_eq!(liability.promisor, bob.account());
+    assert!(net.wait_for(TIMEOUT, |_| alice.reserved_balance(&alice.account()) == cost));
+
+    let bob_balance = bob.free_balance(&bob.account());
+    let result = b"QmYkCXtFCNpcdkmkgDHFMDBGmpPHXLd2ua3UHPBx8zDHtd".to_vec();
+    let finalize = bob.sign(Call::Robonomics(RobonomicsCall::finalize(index, result.clone())));
+    let finalize_fee = bob.estimate_fee(&finalize).fee;
+    bob.submit(finalize).expect("finalize submitted");
+
+    let (block, finalized) = net.wait_for_event(0, from, TIMEOUT, |e| match e {
+        RawEvent::Finalized(i, r) if *i == index => Some(r.clone()),
+        _ => None,
+    }).expect("liability finalized");
+    assert_eq!(finalized, result);
+
+    // Protocol fee goes to treasury, rest of cost is paid to promisor
+    let protocol_fee = alice.robonomics_events(block).iter()
+        .find_map(|e| match e {
+            RawEvent::FeeCollected(i, fee) if *i == index => Some(*fee),
+            _ => None,
+        })
+        .unwrap_or(0);
+    let payment = cost - protocol_fee;
+
+    assert!(net.wait_for(TIMEOUT, |_| bob.best_number() >= block));
+    assert_eq!(alice.reserved_balance(&alice.account()), 0);
+    assert!(alice_balance - alice.free_balance(&alice.account()) >= cost);
+
+    // Fee multiplier could be changed between estimation and inclusion
+    let bob_income = bob.free_balance(&bob.account()) - bob_balance;
+    assert!(bob_income <= payment);
+    assert!(bob_income + 2 * finalize_fee >= payment);
+}
@@ -0,0 +1,85 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! ROS bridge of local testnet with stubbed ROS master: demand sent into ROS topic
+//! is matched with offer and liability is published back into ROS.
+#![cfg(feature = "ros")]
+
+use std::{sync::mpsc, time::Duration};
+use node_cli::{RunParams, service, chain_spec::local_testnet_config};
+use node_runtime::{
+    Call, RobonomicsCall,
+    robonomics::{RawEvent, Capacity},
+    constants::currency::XRT,
+};
+use node_testing::{TestNet, ros_master::StubMaster};
+use msgs::substrate_ros_msgs::{Order, Liability};
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+const MASTER_PORT: u16 = 11_411;
+
+#[test]
+fn ros_demand_creates_liability() {
+    let master = StubMaster::start(MASTER_PORT);
+
+    // ROS node is process wide, so bridges of nodes are separated by namespace
+    let seeds = ["Alice", "Bob"];
+    let net = TestNet::new(local_testnet_config(), &seeds, |index, config| {
+        let namespace = format!("/{}", seeds[index].to_lowercase());
+        let run_params = RunParams { ros_namespace: Some(namespace), ..Default::default() };
+        service::new_full(config, run_params)
+    }).expect("test network started");
+    assert!(net.wait_for_blocks(2, TIMEOUT), "blocks aren't produced");
+    let topics = master.subscribed_topics();
+    assert!(topics.contains(&"/alice/liability/demand/send".to_string()));
+    assert!(topics.contains(&"/bob/liability/demand/send".to_string()));
+
+    let (alice, bob) = (&net.nodes[0], &net.nodes[1]);
+    let (liability_tx, liability_rx) = mpsc::channel();
+    let _incoming = rosrust::subscribe("/alice/liability/incoming", 10, move |l: Liability| {
+        let _ = liability_tx.send(l);
+    }).expect("liability subscriber");
+    let demand = rosrust::publish("/alice/liability/demand/send", 10).expect("demand publisher");
+    assert!(net.wait_for(TIMEOUT, |_| demand.subscriber_count() > 0), "bridge isn't connected");
+
+    let model = "QmWboFP8XeBtFMbNYK3Ne8Z3gKFBSR5iQzkKgeNgQz3dz4";
+    let objective = "QmaCPDxAMMLqA4cGpVRtPfGwv7yqr9sM3URD8GbwgS4mTF";
+    let cost = XRT;
+    let from = alice.best_number();
+
+    let mut order = Order::default();
+    order.model = model.into();
+    order.objective = objective.into();
+    order.cost = cost.to_string();
+    demand.send(order).expect("demand sent");
+
+    bob.submit_call(Call::Robonomics(RobonomicsCall::offer(
+        bs58::decode(model).into_vec().unwrap(),
+        bs58::decode(objective).into_vec().unwrap(),
+        cost, None, Capacity::Limited(1), None,
+    ))).expect("offer submitted");
+
+    let (_, promisee) = net.wait_for_event(0, from, TIMEOUT, |e| match e {
+        RawEvent::NewLiability(_, liability) => Some(liability.promisee.clone()),
+        _ => None,
+    }).expect("liability created");
+    assert_eq!(promisee, alice.account());
+
+    let liability = liability_rx.recv_timeout(TIMEOUT).expect("liability published into ROS");
+    assert_eq!(liability.order.model, model);
+    assert_eq!(liability.order.objective, objective);
+}
@@ -0,0 +1,46 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Runtime APIs used by Substrate ROS bridge. This can be compiled with `#[no_std]`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Encode, Decode};
+use sr_primitives::{
+    traits::Block as BlockT,
+    weights::{Weight, DispatchClass},
+};
+
+/// Transaction fee estimation.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct FeeEstimate<Balance> {
+    /// Dispatch weight.
+    pub weight: Weight,
+    /// Dispatch class.
+    pub class: DispatchClass,
+    /// Final fee: length and weight fee with current multiplier, tip included.
+    pub fee: Balance,
+}
+
+client::decl_runtime_apis! {
+    /// The API to estimate transaction fee.
+    pub trait FeeApi<Balance> where Balance: Codec {
+        /// Get weight, dispatch class and final fee of given extrinsic.
+        fn estimate_fee(extrinsic: <Block as BlockT>::Extrinsic) -> FeeEstimate<Balance>;
+    }
+}
@@ -29,17 +29,24 @@ rosmsg_include!(
     // substrate robonomics
     substrate_ros_msgs / Demand,
     substrate_ros_msgs / Offer,
+    substrate_ros_msgs / OfferOrder,
     substrate_ros_msgs / Finalize,
     substrate_ros_msgs / Liability,
 
     // substrate rpc
     substrate_ros_msgs / ExHash,
     substrate_ros_msgs / BlockHash,
+    substrate_ros_msgs / DigestItem,
+    substrate_ros_msgs / Header,
+    substrate_ros_msgs / Block,
     substrate_ros_msgs / RawExtrinsic,
+    substrate_ros_msgs / TxStatus,
 
     substrate_ros_msgs / PendingExtrinsics,
     substrate_ros_msgs / RemoveExtrinsic,
     substrate_ros_msgs / SubmitExtrinsic,
+    substrate_ros_msgs / SubmitAndWatch,
+    substrate_ros_msgs / EstimateFee,
 
     substrate_ros_msgs / GetBlock,
     substrate_ros_msgs / GetBlockHash,
@@ -51,11 +58,21 @@ rosmsg_include!(
     substrate_ros_msgs / SystemHealthInfo,
 
     substrate_ros_msgs / StorageKey,
+    substrate_ros_msgs / StorageChangeSet,
     substrate_ros_msgs / StateCall,
     substrate_ros_msgs / StorageHash,
     substrate_ros_msgs / StorageKeys,
     substrate_ros_msgs / StorageQuery,
     substrate_ros_msgs / StorageSize,
+    substrate_ros_msgs / SubscribeStorage,
+    substrate_ros_msgs / UnsubscribeStorage,
 
     substrate_ros_msgs / StartLiability,
 );
+
+/// Topic or service name in given namespace, root namespace when empty.
+///
+/// E.g. `chain/best_hash` in namespace `/robot1` is `/robot1/chain/best_hash`.
+pub fn ros_name(namespace: &str, name: &str) -> String {
+    format!("{}/{}", namespace.trim_end_matches('/'), name)
+}
@@ -19,6 +19,7 @@
 
 use std::{
     fs::File,
+    path::PathBuf,
     sync::{Arc, Mutex},
     collections::HashMap,
 };
@@ -29,6 +30,7 @@ use futures::{
     compat::Stream01CompatExt,
 };
 use msgs::{
+    ros_name,
     substrate_ros_msgs::{Liability,
                          StartLiabilityPlayer, StartLiabilityPlayerRes},
 };
@@ -36,22 +38,50 @@ use rosrust::api::error::Error;
 use log::error;
 use futures::executor::block_on;
 
-use crate::rosbag_player::RosbagPlayer;
+use node_metrics as metrics;
+
+mod player;
+use crate::player::RosbagPlayer;
 
 /// ROS Pub/Sub queue size.
 /// http://wiki.ros.org/roscpp/Overview/Publishers%20and%20Subscribers#Queueing_and_Lazy_Deserialization
 const QUEUE_SIZE: usize = 10;
 
-const LIABILITY_PREPARE_FOR_EXECUTION_TOPIC_NAME: &str = "/liability/prepare";
+const LIABILITY_PREPARE_FOR_EXECUTION_TOPIC_NAME: &str = "liability/prepare";
 const LIABILITY_READY_TOPIC_NAME: &str = "liability/ready";
-const LIABILITY_START_SRV_NAME: &str = "/liability/start";
+const LIABILITY_START_SRV_NAME: &str = "liability/start";
+
+/// Liability engine options.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// IPFS API host.
+    pub ipfs_host: String,
+    /// IPFS API port.
+    pub ipfs_port: u16,
+    /// Directory of downloaded rosbag files.
+    pub bag_dir: PathBuf,
+    /// Namespace of engine topics and services, root namespace when empty.
+    pub namespace: String,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            ipfs_host: "localhost".into(),
+            ipfs_port: 5001,
+            bag_dir: PathBuf::from("."),
+            namespace: String::new(),
+        }
+    }
+}
 
 async fn add_liability(
     liability: Liability,
+    ipfs: &IpfsClient,
+    bag_dir: &PathBuf,
     known_liabilities: &Arc<Mutex<HashMap<u64, RosbagPlayer>>>,
     publisher: &Arc<rosrust::Publisher<Liability>>
 ) {
-    let ipfs = IpfsClient::default();
     let l = liability.clone();
     let bag_hash = liability.order.objective;
     let liability_id = liability.id;
@@ -60,12 +90,13 @@ async fn add_liability(
     if ! (liabilities.contains_key(&liability_id)) {
         let (response, _) = ipfs.cat(bag_hash.as_str()).compat().into_future().await;
         if let Some(Ok(content)) = response {
-            let bag_file = File::create(bag_hash.as_str()).expect("could not create file");
+            let bag_path = bag_dir.join(&bag_hash);
+            let bag_file = File::create(&bag_path).expect("could not create file");
             let mut buffer = AllowStdIo::new(bag_file);
             buffer.write_all(&content).await;
             buffer.close().await;
 
-            let player = RosbagPlayer::new(bag_hash);
+            let player = RosbagPlayer::new(bag_path.to_string_lossy().into_owned());
             liabilities.insert(liability_id, player);
             publisher.send(l).unwrap();
         } else {
@@ -82,26 +113,36 @@ async fn launch_liability_player(
 ) {
     let mut data = known_liabilities.lock().unwrap();
     let mut player = data.remove(&liability_id).unwrap();
+    let timer = metrics::ROSBAG_PLAYBACK_DURATION.start_timer();
     player.play().await;
+    timer.observe_duration();
 }
 
-pub fn start_liability_engine()
+pub fn start_liability_engine(config: EngineConfig)
     -> Result<(Vec<rosrust::Service>, Vec<rosrust::Subscriber>), Error> {
     let mut services = vec![];
     let mut subscribers = vec![];
 
+    let ipfs = IpfsClient::new(&config.ipfs_host, config.ipfs_port)
+        .map_err(|e| format!("Invalid IPFS API address: {}", e))?;
+    let bag_dir = config.bag_dir;
+    let namespace = config.namespace;
+
     let liability_players = Arc::new(Mutex::new(HashMap::new()));
-    let liability_ready_pub = Arc::new(rosrust::publish(LIABILITY_READY_TOPIC_NAME, QUEUE_SIZE).unwrap());
+    let liability_ready_pub = Arc::new(
+        rosrust::publish(&ros_name(&namespace, LIABILITY_READY_TOPIC_NAME), QUEUE_SIZE).unwrap()
+    );
 
     let players01 = liability_players.clone();
     subscribers.push(
-        rosrust::subscribe(LIABILITY_PREPARE_FOR_EXECUTION_TOPIC_NAME, QUEUE_SIZE, move |l: Liability| {
-            block_on(add_liability(l.clone(), &players01, &liability_ready_pub));
+        rosrust::subscribe(&ros_name(&namespace, LIABILITY_PREPARE_FOR_EXECUTION_TOPIC_NAME), QUEUE_SIZE, move |l: Liability| {
+            block_on(add_liability(l.clone(), &ipfs, &bag_dir, &players01, &liability_ready_pub));
         }).expect("failed to create incoming liability subscriber")
     );
 
     let players02 = liability_players.clone();
-    services.push(rosrust::service::<StartLiabilityPlayer, _>(LIABILITY_START_SRV_NAME, move |req| {
+    let start_srv_name = ros_name(&namespace, LIABILITY_START_SRV_NAME);
+    services.push(rosrust::service::<StartLiabilityPlayer, _>(&start_srv_name, move |req| {
         let mut res = StartLiabilityPlayerRes::default();
         block_on(launch_liability_player(req.id, &players02));
         res.success = true;
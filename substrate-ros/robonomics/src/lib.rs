@@ -18,4 +18,5 @@
 //! This module exports Robonomics API into ROS namespace.
 
 mod ros_api;
-pub use ros_api::start_api;
+pub use ros_api::{start_api, start_light_api};
+pub use robonomics_liability::{start_liability_engine, EngineConfig};
@@ -17,65 +17,126 @@
 ///////////////////////////////////////////////////////////////////////////////
 //! This module exports Robonomics API into ROS namespace.
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::sync::Arc;
 use rosrust::api::error;
-use futures::{prelude::*, channel::mpsc};
-use client::{blockchain::HeaderBackend, BlockchainEvents};
+use futures::{prelude::*, channel::mpsc, compat::Future01CompatExt};
+use futures01::IntoFuture;
+use client::{
+    blockchain::HeaderBackend, BlockchainEvents,
+    light::fetcher::{Fetcher, RemoteReadRequest},
+};
 use sr_primitives::{
     codec::{Decode, Encode},
     generic::{BlockId, Era},
     traits::{Header, ProvideRuntimeApi},
+    transaction_validity::InvalidTransaction,
 };
 use primitives::{
     blake2_256, twox_128, sr25519,
     storage::{StorageKey, StorageData},
     crypto::Pair, crypto::Ss58Codec,
 };
-use transaction_pool::txpool::{ChainApi, Pool, ExtrinsicFor};
+use transaction_pool::txpool::{self, ChainApi, Pool, ExtrinsicFor, IntoPoolError};
 use node_runtime::{
     Call, UncheckedExtrinsic, EventRecord, Event,
-    types::{Block, Hash, AccountNonceApi},
-    robonomics::*, RobonomicsCall, Runtime
+    constants::validity::{SPONSOR_ALLOWANCE_EXHAUSTED, RATE_LIMIT_EXCEEDED, NOT_LIABILITY_PARTY},
+    types::{Block, Hash, AccountId, AccountNonceApi, IdentityApi},
+    robonomics::*, sponsorship, RobonomicsCall, ProxyCall, Runtime, VERSION,
 };
+use node_metrics as metrics;
+use ros_rpc::chain::header_msg;
 
-use msgs::substrate_ros_msgs;
-use msgs::std_msgs;
+use msgs::{ros_name, substrate_ros_msgs, std_msgs};
 
 /// ROS Pub/Sub queue size.
 /// http://wiki.ros.org/roscpp/Overview/Publishers%20and%20Subscribers#Queueing_and_Lazy_Deserialization
 const QUEUE_SIZE: usize = 10;
 
-const BEST_HASH_ROS_TOPIC_NAME: &str = "/chain/best_hash";
-const BEST_NUMBER_ROS_TOPIC_NAME: &str = "/chain/best_number";
-const FINALIZED_HASH_ROS_TOPIC_NAME: &str = "/chain/finalized_hash";
-const FINALIZED_NUMBER_ROS_TOPIC_NAME: &str = "/chain/finalized_number";
+const BEST_HASH_ROS_TOPIC_NAME: &str = "chain/best_hash";
+const BEST_NUMBER_ROS_TOPIC_NAME: &str = "chain/best_number";
+const BEST_HEADER_ROS_TOPIC_NAME: &str = "chain/best_header";
+const FINALIZED_HASH_ROS_TOPIC_NAME: &str = "chain/finalized_hash";
+const FINALIZED_NUMBER_ROS_TOPIC_NAME: &str = "chain/finalized_number";
+const FINALIZED_HEADER_ROS_TOPIC_NAME: &str = "chain/finalized_header";
+const REJECTED_ROS_TOPIC_NAME: &str = "liability/rejected";
+const DEMAND_INCOMING_ROS_TOPIC_NAME: &str = "liability/demand/incoming";
+const OFFER_INCOMING_ROS_TOPIC_NAME: &str = "liability/offer/incoming";
+const LIABILITY_INCOMING_ROS_TOPIC_NAME: &str = "liability/incoming";
+const DEMAND_SEND_ROS_TOPIC_NAME: &str = "liability/demand/send";
+const OFFER_SEND_ROS_TOPIC_NAME: &str = "liability/offer/send";
+const STANDING_OFFER_SEND_ROS_TOPIC_NAME: &str = "liability/offer/send_standing";
+const FINALIZE_ROS_TOPIC_NAME: &str = "liability/finalize";
+
+/// Publish message into ROS topic, failures are logged and counted.
+fn publish<T: rosrust::Message>(publisher: &rosrust::Publisher<T>, msg: T) {
+    if let Err(e) = publisher.send(msg) {
+        warn!("Unable to publish ROS message: {}", e);
+        metrics::ROS_PUBLISH_ERRORS.inc();
+    }
+}
+
+/// Human readable reason of transaction pool rejection.
+fn rejection_reason<E: IntoPoolError>(error: E) -> String {
+    match error.into_pool_error() {
+        Ok(txpool::error::Error::InvalidTransaction(InvalidTransaction::Custom(SPONSOR_ALLOWANCE_EXHAUSTED))) =>
+            "sponsor allowance exhausted".to_string(),
+        Ok(txpool::error::Error::InvalidTransaction(InvalidTransaction::Custom(RATE_LIMIT_EXCEEDED))) =>
+            "rate limit exceeded".to_string(),
+        Ok(txpool::error::Error::InvalidTransaction(InvalidTransaction::Custom(NOT_LIABILITY_PARTY))) =>
+            "sender isn't liability party".to_string(),
+        Ok(e) => format!("{:?}", e),
+        Err(e) => format!("{:?}", e),
+    }
+}
 
-/// Robonomics extrinsic sender.
+/// Robonomics extrinsic sender, calls are wrapped into proxy call when real account is given.
 fn extrinsic_stream<C, P>(
     client: Arc<C>,
     pool: Arc<Pool<P>>,
     stream: mpsc::UnboundedReceiver<RobonomicsCall<Runtime>>,
     key: sr25519::Pair,
+    proxy_for: Option<AccountId>,
+    namespace: &str,
 ) -> impl Future<Output=()> where
     C: ProvideRuntimeApi + HeaderBackend<Block>,
     P: ChainApi<Block=Block>,
     C::Api: AccountNonceApi<Block>,
 {
+    let rejected_pub = rosrust::publish(&ros_name(namespace, REJECTED_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+
     stream.for_each(move |call| {
         let api = client.runtime_api();
         let block_id = BlockId::hash(client.info().best_hash);
         // TODO: also check transaction pool for pending txs
-        let nonce = api.account_nonce(&block_id, key.public()).unwrap(); 
+        let nonce = match api.account_nonce(&block_id, key.public()) {
+            Ok(nonce) => nonce,
+            Err(e) => {
+                warn!("Unable to get account nonce: {:?}", e);
+                metrics::EXTRINSICS_FAILED.inc();
+                return future::ready(());
+            }
+        };
         let check_version = system::CheckVersion::new();
         let check_genesis = system::CheckGenesis::new();
 		let check_era = system::CheckEra::from(Era::Immortal);
 		let check_nonce = system::CheckNonce::from(nonce);
 		let check_weight = system::CheckWeight::new();
-		let take_fees = balances::TakeFees::from(0);
-
-		let extra = (check_version, check_genesis, check_era, check_nonce, check_weight, take_fees); 
-		let raw_payload = (Call::Robonomics(call), extra.clone(), client.info().genesis_hash);
+		let take_fees = sponsorship::TakeSponsoredFees::from(0);
+		let check_rate_limit = CheckRateLimit::new();
+		let check_party = CheckLiabilityParty::new();
+
+		let extra = (
+			check_version, check_genesis, check_era, check_nonce, check_weight,
+			take_fees, check_rate_limit, check_party,
+		);
+		let call = match proxy_for {
+			Some(ref real) => Call::Proxy(ProxyCall::proxy(real.clone(), Box::new(Call::Robonomics(call)))),
+			None => Call::Robonomics(call),
+		};
+		let genesis_hash = client.info().genesis_hash;
+		let additional_signed = (VERSION.spec_version, genesis_hash, genesis_hash, (), (), (), (), ());
+		let raw_payload = (call, extra.clone(), additional_signed);
 
 		let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
 			key.sign(&blake2_256(payload)[..])
@@ -89,27 +150,55 @@ fn extrinsic_stream<C, P>(
 			signature.into(),
 			extra,
 		).encode();
-        let xt: ExtrinsicFor<P> = Decode::decode(&mut extrinsic.as_slice()).unwrap();
-
-        let res = pool.submit_one(&block_id, xt);
-        debug!("txpool submit result: {:?}", res); 
+        let xt: ExtrinsicFor<P> = match Decode::decode(&mut extrinsic.as_slice()) {
+            Ok(xt) => xt,
+            Err(e) => {
+                warn!("Unable to decode robonomics extrinsic: {:?}", e);
+                metrics::EXTRINSICS_FAILED.inc();
+                return future::ready(());
+            }
+        };
+
+        match pool.submit_one(&block_id, xt) {
+            Ok(hash) => {
+                debug!("txpool submit result: {:?}", hash);
+                metrics::EXTRINSICS_SUBMITTED.inc();
+            },
+            Err(e) => {
+                let reason = rejection_reason(e);
+                warn!("robonomics extrinsic rejected: {}", reason);
+                metrics::EXTRINSICS_REJECTED.inc();
+
+                let mut msg = std_msgs::String::default();
+                msg.data = reason;
+                publish(&rejected_pub, msg);
+            }
+        }
         future::ready(())
     })
 }
 
-/// Storage event listener.
-fn event_stream<C>(
+/// Verified display name of account at given block, empty string when unknown.
+fn display_name<C>(client: &C, block: Hash, account: &AccountId) -> String where
+    C: ProvideRuntimeApi,
+    C::Api: IdentityApi<Block>,
+{
+    client.runtime_api().display_name(&BlockId::hash(block), account.clone())
+        .ok()
+        .and_then(|name| name)
+        .map(|name| String::from_utf8_lossy(&name).into_owned())
+        .unwrap_or_default()
+}
+
+/// Block events of full client, taken from storage change notifications.
+fn storage_events<C>(
     client: Arc<C>,
-) -> impl Future<Output=()> where
+) -> impl Stream<Item=(Hash, Vec<EventRecord<Event, Hash>>)> where
     C: BlockchainEvents<Block>,
 {
-    let demand_pub = rosrust::publish("liability/demand/incoming", QUEUE_SIZE).unwrap();
-    let offer_pub = rosrust::publish("liability/offer/incoming", QUEUE_SIZE).unwrap();
-    let liability_pub = rosrust::publish("liability/incoming", QUEUE_SIZE).unwrap();
-
     let events_key = StorageKey(twox_128(b"System Events").to_vec());
     client.storage_changes_notification_stream(Some(&[events_key]), None).unwrap()
-        .for_each(move |(_, changes)| {
+        .map(|(block, changes)| {
             // Decode events from change set
             let records: Vec<Vec<EventRecord<Event, Hash>>> = changes.iter()
                 .filter_map(|(_, _, mbdata)| if let Some(StorageData(data)) = mbdata {
@@ -118,13 +207,66 @@ fn event_stream<C>(
                     } else { None }
                 } else { None })
                 .collect();
-            let events: Vec<Event> = records.concat().iter().cloned().map(|r| r.event).collect();
+            (block, records.concat())
+        })
+}
+
+/// Block events of light client, requested from full nodes for each new best block.
+fn remote_events<C, F>(
+    client: Arc<C>,
+    fetcher: Arc<F>,
+) -> impl Stream<Item=(Hash, Vec<EventRecord<Event, Hash>>)> where
+    C: BlockchainEvents<Block>,
+    F: Fetcher<Block>,
+{
+    let events_key = twox_128(b"System Events").to_vec();
+    client.import_notification_stream()
+        .filter(|block| future::ready(block.is_new_best))
+        .then(move |block| {
+            let hash = block.hash;
+            let request = RemoteReadRequest {
+                block: hash,
+                header: block.header,
+                key: events_key.clone(),
+                retry_count: None,
+            };
+            fetcher.remote_read(request).into_future().compat()
+                .map(move |data| (hash, data))
+        })
+        .filter_map(|(hash, data)| future::ready(match data {
+            Ok(Some(data)) => Decode::decode(&mut &data[..]).ok().map(|records| (hash, records)),
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Unable to fetch events of block {:?}: {:?}", hash, e);
+                None
+            }
+        }))
+}
+
+/// Robonomics event listener.
+fn event_stream<C, S>(
+    client: Arc<C>,
+    events: S,
+    namespace: &str,
+) -> impl Future<Output=()> where
+    C: ProvideRuntimeApi,
+    C::Api: IdentityApi<Block>,
+    S: Stream<Item=(Hash, Vec<EventRecord<Event, Hash>>)>,
+{
+    let demand_pub = rosrust::publish(&ros_name(namespace, DEMAND_INCOMING_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+    let offer_pub = rosrust::publish(&ros_name(namespace, OFFER_INCOMING_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+    let liability_pub = rosrust::publish(&ros_name(namespace, LIABILITY_INCOMING_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+
+    events
+        .for_each(move |(block, records)| {
+            let events: Vec<Event> = records.into_iter().map(|r| r.event).collect();
 
             // Iterate and dispatch events
             for event in events {
                 if let Event::robonomics(e) = event { match e {
                     RawEvent::NewDemand(hash, demand) => {
                         debug!("NewDemand: {:?} {:?}", hash, demand);
+
                         let mut msg = substrate_ros_msgs::Demand::default();
                         let model = bs58::encode(&demand.order.model);
                         let objective = bs58::encode(&demand.order.objective);
@@ -133,12 +275,14 @@ fn event_stream<C>(
                         msg.order.objective = objective.into_string();
                         msg.order.cost      = demand.order.cost.to_string();
                         msg.sender          = demand.sender.to_ss58check();
+                        msg.sender_name     = display_name(&*client, block, &demand.sender);
 
-                        demand_pub.send(msg).expect("Unable to send NewDemand event message");
+                        publish(&demand_pub, msg);
                     },
 
                     RawEvent::NewOffer(hash, offer) => {
                         debug!("NewOffer: {:?} {:?}", hash, offer);
+
                         let mut msg = substrate_ros_msgs::Offer::default();
                         let model = bs58::encode(&offer.order.model);
                         let objective = bs58::encode(&offer.order.objective);
@@ -147,12 +291,19 @@ fn event_stream<C>(
                         msg.order.objective = objective.into_string();
                         msg.order.cost      = offer.order.cost.to_string();
                         msg.sender          = offer.sender.to_ss58check();
-
-                        offer_pub.send(msg).expect("Unable to send NewOffer event message");
+                        msg.sender_name     = display_name(&*client, block, &offer.sender);
+                        msg.capacity        = match offer.capacity {
+                            Capacity::Limited(n) => n,
+                            Capacity::Unlimited => 0,
+                        };
+                        msg.expires         = offer.expires.unwrap_or(0);
+
+                        publish(&offer_pub, msg);
                     },
 
                     RawEvent::NewLiability(id, liability) => {
                         debug!("NewLiability: {:?} {:?}", id, liability);
+
                         let mut msg = substrate_ros_msgs::Liability::default();
                         let model = bs58::encode(&liability.order.model);
                         let objective = bs58::encode(&liability.order.objective);
@@ -163,34 +314,49 @@ fn event_stream<C>(
                         msg.order.cost      = liability.order.cost.to_string();
                         msg.promisee        = liability.promisee.to_ss58check();
                         msg.promisor        = liability.promisor.to_ss58check();
+                        msg.promisee_name   = display_name(&*client, block, &liability.promisee);
+                        msg.promisor_name   = display_name(&*client, block, &liability.promisor);
+
+                        publish(&liability_pub, msg);
+                    },
 
-                        liability_pub.send(msg.clone()).expect("Unable to send NewLiability event message");
+                    RawEvent::OfferClosed(hash, _) => {
+                        debug!("OfferClosed: {:?}", hash);
+                    },
+
+                    RawEvent::Finalized(id, _) => {
+                        debug!("Finalized: {:?}", id);
                     },
 
                     _ => ()
                 }}
             }
+
             future::ready(())
         })
 }
 
 fn import_notification_stream<C>(
     client: Arc<C>,
+    namespace: &str,
 ) -> impl Future<Output=()> where
     C: BlockchainEvents<Block> + HeaderBackend<Block>,
 {
-    let hash_pub = rosrust::publish(BEST_HASH_ROS_TOPIC_NAME, QUEUE_SIZE).unwrap();
-    let number_pub = rosrust::publish(BEST_NUMBER_ROS_TOPIC_NAME, QUEUE_SIZE).unwrap();
+    let hash_pub = rosrust::publish(&ros_name(namespace, BEST_HASH_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+    let number_pub = rosrust::publish(&ros_name(namespace, BEST_NUMBER_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+    let header_pub = rosrust::publish(&ros_name(namespace, BEST_HEADER_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
 
     client.import_notification_stream().for_each(move |block| {
         if block.is_new_best {
             let mut hash_msg = substrate_ros_msgs::BlockHash::default();
             hash_msg.data = block.hash.into();
-            hash_pub.send(hash_msg).unwrap();
+            publish(&hash_pub, hash_msg);
 
             let mut number_msg = std_msgs::UInt64::default();
             number_msg.data = (*block.header.number()).into();
-            number_pub.send(number_msg).unwrap();
+            publish(&number_pub, number_msg);
+
+            publish(&header_pub, header_msg(&block.header));
         }
         future::ready(())
     })
@@ -198,45 +364,89 @@ fn import_notification_stream<C>(
 
 fn finality_notification_stream<C>(
     client: Arc<C>,
+    namespace: &str,
 ) -> impl Future<Output=()> where
     C: BlockchainEvents<Block> + HeaderBackend<Block>,
 {
-    let finalized_number_pub = rosrust::publish(FINALIZED_NUMBER_ROS_TOPIC_NAME, QUEUE_SIZE).unwrap();
-    let finalized_hash_pub = rosrust::publish(FINALIZED_HASH_ROS_TOPIC_NAME, QUEUE_SIZE).unwrap();
+    let finalized_number_pub = rosrust::publish(&ros_name(namespace, FINALIZED_NUMBER_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+    let finalized_hash_pub = rosrust::publish(&ros_name(namespace, FINALIZED_HASH_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
+    let finalized_header_pub = rosrust::publish(&ros_name(namespace, FINALIZED_HEADER_ROS_TOPIC_NAME), QUEUE_SIZE).unwrap();
 
     client.finality_notification_stream().for_each(move |block| {
         let mut finalized_number_msg = std_msgs::UInt64::default();
         finalized_number_msg.data = (*block.header.number()).into();
-        finalized_number_pub.send(finalized_number_msg).unwrap();
+        publish(&finalized_number_pub, finalized_number_msg);
 
         let mut finalized_hash_msg = substrate_ros_msgs::BlockHash::default();
         finalized_hash_msg.data = block.hash.into();
-        finalized_hash_pub.send(finalized_hash_msg).unwrap();
+        publish(&finalized_hash_pub, finalized_hash_msg);
+
+        publish(&finalized_header_pub, header_msg(&block.header));
 
         future::ready(())
     })
 }
 
-/// ROS API main routine.
+/// ROS API main routine, topics are placed into given namespace.
 pub fn start_api<C, P>(
     client: Arc<C>,
     pool: Arc<Pool<P>>,
     key: sr25519::Pair,
+    proxy_for: Option<AccountId>,
+    namespace: &str,
 ) -> Result<(impl Future<Output=()>, Vec<rosrust::Subscriber>), error::Error> where
     C: ProvideRuntimeApi + HeaderBackend<Block> + BlockchainEvents<Block>,
     P: ChainApi<Block=Block>,
-    C::Api: AccountNonceApi<Block>,
+    C::Api: AccountNonceApi<Block> + IdentityApi<Block>,
 {
-    info!("ROS API account is {:?}", key.public().to_ss58check());
-    rosrust::try_init_with_options("robonomics", false)?;
+    let events = storage_events(client.clone());
+    start(client, pool, key, proxy_for, namespace, events)
+}
 
+/// ROS API main routine for light client: events are requested by remote fetcher,
+/// extrinsics are submitted into light transaction pool.
+pub fn start_light_api<C, P, F>(
+    client: Arc<C>,
+    pool: Arc<Pool<P>>,
+    key: sr25519::Pair,
+    proxy_for: Option<AccountId>,
+    namespace: &str,
+    fetcher: Arc<F>,
+) -> Result<(impl Future<Output=()>, Vec<rosrust::Subscriber>), error::Error> where
+    C: ProvideRuntimeApi + HeaderBackend<Block> + BlockchainEvents<Block>,
+    P: ChainApi<Block=Block>,
+    C::Api: AccountNonceApi<Block> + IdentityApi<Block>,
+    F: Fetcher<Block>,
+{
+    let events = remote_events(client.clone(), fetcher);
+    start(client, pool, key, proxy_for, namespace, events)
+}
+
+fn start<C, P, S>(
+    client: Arc<C>,
+    pool: Arc<Pool<P>>,
+    key: sr25519::Pair,
+    proxy_for: Option<AccountId>,
+    namespace: &str,
+    events: S,
+) -> Result<(impl Future<Output=()>, Vec<rosrust::Subscriber>), error::Error> where
+    C: ProvideRuntimeApi + HeaderBackend<Block> + BlockchainEvents<Block>,
+    P: ChainApi<Block=Block>,
+    C::Api: AccountNonceApi<Block> + IdentityApi<Block>,
+    S: Stream<Item=(Hash, Vec<EventRecord<Event, Hash>>)>,
+{
+    info!("ROS API account is {:?}", key.public().to_ss58check());
+    if let Some(ref real) = proxy_for {
+        info!("ROS API is proxy of {:?}", real.to_ss58check());
+    }
     // Create extrinsics channel
     let (demand_tx, extrinsic_rx) = mpsc::unbounded();
     let offer_tx = demand_tx.clone();
+    let standing_offer_tx = demand_tx.clone();
     let finalize_tx = demand_tx.clone();
 
     // Subscribe for sending demand extrinsics
-    let demand = rosrust::subscribe("liability/demand/send", QUEUE_SIZE, move |v: substrate_ros_msgs::Order| {
+    let demand = rosrust::subscribe(&ros_name(namespace, DEMAND_SEND_ROS_TOPIC_NAME), QUEUE_SIZE, move |v: substrate_ros_msgs::Order| {
         let model = bs58::decode(v.model).into_vec().unwrap();
         let objective = bs58::decode(v.objective).into_vec().unwrap();
         let cost = v.cost.parse().unwrap();
@@ -244,26 +454,39 @@ pub fn start_api<C, P>(
     })?;
 
     // Subscribe for sending offer extrinsics
-    let offer = rosrust::subscribe("liability/offer/send", QUEUE_SIZE, move |v: substrate_ros_msgs::Order| {
+    let offer = rosrust::subscribe(&ros_name(namespace, OFFER_SEND_ROS_TOPIC_NAME), QUEUE_SIZE, move |v: substrate_ros_msgs::Order| {
         let model = bs58::decode(v.model).into_vec().unwrap();
         let objective = bs58::decode(v.objective).into_vec().unwrap();
         let cost = v.cost.parse().unwrap();
-        offer_tx.unbounded_send(RobonomicsCall::offer(model, objective, cost, None)).unwrap();
+        offer_tx.unbounded_send(RobonomicsCall::offer(model, objective, cost, None, Capacity::default(), None)).unwrap();
+    })?;
+
+    // Subscribe for sending standing offer extrinsics
+    let standing_offer = rosrust::subscribe(&ros_name(namespace, STANDING_OFFER_SEND_ROS_TOPIC_NAME), QUEUE_SIZE, move |v: substrate_ros_msgs::OfferOrder| {
+        let model = bs58::decode(v.order.model).into_vec().unwrap();
+        let objective = bs58::decode(v.order.objective).into_vec().unwrap();
+        let cost = v.order.cost.parse().unwrap();
+        let capacity = match v.capacity {
+            0 => Capacity::Unlimited,
+            n => Capacity::Limited(n),
+        };
+        let validity = if v.validity > 0 { Some(v.validity) } else { None };
+        standing_offer_tx.unbounded_send(RobonomicsCall::offer(model, objective, cost, None, capacity, validity)).unwrap();
     })?;
 
     // Finalize liability
-    let finalize = rosrust::subscribe("liability/finalize", QUEUE_SIZE, move |v: substrate_ros_msgs::Finalize| {
+    let finalize = rosrust::subscribe(&ros_name(namespace, FINALIZE_ROS_TOPIC_NAME), QUEUE_SIZE, move |v: substrate_ros_msgs::Finalize| {
         let result = bs58::decode(v.result).into_vec().unwrap();
         finalize_tx.unbounded_send(RobonomicsCall::finalize(v.id, result)).unwrap();
     })?;
 
     // Store subscribers in vector
-    let extrinsics = extrinsic_stream(client.clone(), pool, extrinsic_rx, key);
-    let events     = event_stream(client.clone());
-    let status     = import_notification_stream(client.clone());
-    let finality   = finality_notification_stream(client);
+    let extrinsics = extrinsic_stream(client.clone(), pool, extrinsic_rx, key, proxy_for, namespace);
+    let events     = event_stream(client.clone(), events, namespace);
+    let status     = import_notification_stream(client.clone(), namespace);
+    let finality   = finality_notification_stream(client, namespace);
 
-    let subscriptions = vec![demand, offer, finalize];
+    let subscriptions = vec![demand, offer, standing_offer, finalize];
     let task = future::join4(extrinsics, events, status, finality).map(|_| ()); 
 
     Ok((task, subscriptions))
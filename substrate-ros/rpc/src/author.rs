@@ -16,55 +16,103 @@
 //
 ///////////////////////////////////////////////////////////////////////////////
 
-use std::sync::Arc;
-use client::{self, Client};
-use codec::{Encode, Decode};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    marker::PhantomData,
+    sync::{Arc, atomic::{AtomicU64, Ordering}},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use log::warn;
+use futures::{
+    prelude::*,
+    channel::mpsc,
+    future,
+    stream::SelectAll,
+    task::Poll,
+};
+use client::{self, Client, BlockchainEvents};
+use codec::{Codec, Encode, Decode};
 use primitives::{Bytes, Blake2Hasher, H256};
-use runtime_primitives::{generic, traits};
+use runtime_primitives::{generic, traits::{self, Header, ProvideRuntimeApi}};
+use substrate_ros_api::{FeeApi, FeeEstimate};
 use transaction_pool::{
 	txpool::{
 		ChainApi as PoolChainApi,
 		ExHash as ExHashT,
 		Pool,
+		watcher::{Watcher, Status},
 	},
 };
+use msgs::ros_name;
 use msgs::substrate_ros_msgs::{
-    ExHash, RawExtrinsic,
+    ExHash, RawExtrinsic, TxStatus,
     SubmitExtrinsic, SubmitExtrinsicRes,
+    SubmitAndWatch, SubmitAndWatchRes,
     PendingExtrinsics, PendingExtrinsicsRes,
     RemoveExtrinsic, RemoveExtrinsicRes,
+    EstimateFee, EstimateFeeRes,
 };
 use rosrust::api::error::Error;
 use crate::traits::RosRpc;
 
-const SUBMIT_SRV_NAME: &str = "/author/submit_extrinsic";
-const REMOVE_SRV_NAME: &str = "/author/remove_extrinsic";
-const PENDING_SRV_NAME: &str = "/author/pending_extrinsics";
+/// ROS Pub/Sub queue size.
+/// http://wiki.ros.org/roscpp/Overview/Publishers%20and%20Subscribers#Queueing_and_Lazy_Deserialization
+const QUEUE_SIZE: usize = 10;
+
+const SUBMIT_SRV_NAME: &str = "author/submit_extrinsic";
+const SUBMIT_AND_WATCH_SRV_NAME: &str = "author/submit_and_watch";
+const TX_STATUS_TOPIC_NAME: &str = "author/tx_status";
+const REMOVE_SRV_NAME: &str = "author/remove_extrinsic";
+const PENDING_SRV_NAME: &str = "author/pending_extrinsics";
+const ESTIMATE_FEE_SRV_NAME: &str = "author/estimate_fee";
+
+/// Status watcher of submitted extrinsic.
+pub type TxWatcher = Watcher<H256, H256>;
+
+/// Watched extrinsic: watch id and status watcher.
+pub type TxWatch = (u64, TxWatcher);
 
 /// Authoring API
-pub struct Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'static {
+pub struct Author<B, E, P, RA, Balance> where P: PoolChainApi + Sync + Send + 'static {
     /// Substrate client
     client: Arc<Client<B, E, <P as PoolChainApi>::Block, RA>>,
     /// Transactions pool
     pool: Arc<Pool<P>>,
+    /// Extrinsics submitted by `submit_and_watch`
+    watchers: mpsc::UnboundedSender<TxWatch>,
+    /// Next watch id, seeded by start time to be unique across node restarts
+    next_watch_id: AtomicU64,
+    /// Fee estimation balance type
+    _marker: PhantomData<Balance>,
 }
 
-impl<B, E, P, RA> Author<B, E, P, RA> where
+impl<B, E, P, RA, Balance> Author<B, E, P, RA, Balance> where
 	B: client::backend::Backend<<P as PoolChainApi>::Block, Blake2Hasher> + Send + Sync + 'static,
 	E: client::CallExecutor<<P as PoolChainApi>::Block, Blake2Hasher> + Send + Sync + 'static,
 	P: PoolChainApi<Hash=H256> + Sync + Send + 'static,
 	P::Block: traits::Block<Hash=H256>,
 	P::Error: 'static,
-	RA: Send + Sync + 'static
+	RA: Send + Sync + 'static,
+	Client<B, E, <P as PoolChainApi>::Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, <P as PoolChainApi>::Block, RA> as ProvideRuntimeApi>::Api: FeeApi<<P as PoolChainApi>::Block, Balance>,
+	Balance: Codec + Display + Send + Sync + 'static,
 {
     /// Create new instance of Authoring API.
     pub fn new(
         client: Arc<Client<B, E, <P as PoolChainApi>::Block, RA>>,
         pool: Arc<Pool<P>>,
+        watchers: mpsc::UnboundedSender<TxWatch>,
     ) -> Self {
+        let start_time = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|t| t.as_nanos() as u64)
+            .unwrap_or(0);
         Author {
             client,
             pool,
+            watchers,
+            next_watch_id: AtomicU64::new(start_time),
+            _marker: PhantomData,
         }
     }
 
@@ -76,6 +124,29 @@ impl<B, E, P, RA> Author<B, E, P, RA> where
 			.map_err(|_| "Extrinsic pool error")
 	}
 
+	/// Submit extrinsic and publish its status updates tagged by watch id,
+	/// returns watch id and extrinsic hash.
+	fn submit_and_watch(&self, ext: Bytes) -> Result<(u64, ExHashT<P>), &str> {
+		let xt = Decode::decode(&mut &ext[..]).map_err(|_| "Bad extrinsic format")?;
+		let watch_id = self.next_watch_id.fetch_add(1, Ordering::SeqCst);
+		let best_block_hash = self.client.info().chain.best_hash;
+		let watcher = self.pool
+			.submit_and_watch(&generic::BlockId::hash(best_block_hash), xt)
+			.map_err(|_| "Extrinsic pool error")?;
+		let hash = watcher.hash().clone();
+		self.watchers.unbounded_send((watch_id, watcher))
+			.map_err(|_| "Transaction status publisher is stopped")?;
+		Ok((watch_id, hash))
+	}
+
+	fn estimate_fee(&self, ext: Bytes) -> Result<FeeEstimate<Balance>, &str> {
+		let xt = Decode::decode(&mut &ext[..]).map_err(|_| "Bad extrinsic format")?;
+		let best_block_hash = self.client.info().chain.best_hash;
+		self.client.runtime_api()
+			.estimate_fee(&generic::BlockId::hash(best_block_hash), xt)
+			.map_err(|_| "Runtime API error")
+	}
+
 	fn pending_extrinsics(&self) -> Vec<Bytes> {
 		self.pool.ready().map(|tx| tx.data.encode().into()).collect()
 	}
@@ -88,20 +159,23 @@ impl<B, E, P, RA> Author<B, E, P, RA> where
 	}
 }
 
-impl<B, E, P, RA> RosRpc for Author<B, E, P, RA> where
+impl<B, E, P, RA, Balance> RosRpc for Author<B, E, P, RA, Balance> where
 	B: client::backend::Backend<<P as PoolChainApi>::Block, Blake2Hasher> + Send + Sync + 'static,
 	E: client::CallExecutor<<P as PoolChainApi>::Block, Blake2Hasher> + Send + Sync + 'static,
 	P: PoolChainApi<Hash=H256> + Sync + Send + 'static,
 	P::Block: traits::Block<Hash=H256>,
 	P::Error: 'static,
-	RA: Send + Sync + 'static
+	RA: Send + Sync + 'static,
+	Client<B, E, <P as PoolChainApi>::Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, <P as PoolChainApi>::Block, RA> as ProvideRuntimeApi>::Api: FeeApi<<P as PoolChainApi>::Block, Balance>,
+	Balance: Codec + Display + Send + Sync + 'static,
 {
-    fn start(api: Arc<Self>) -> Result<Vec<rosrust::Service>, Error> {
+    fn start(api: Arc<Self>, namespace: &str) -> Result<Vec<rosrust::Service>, Error> {
         let mut services = vec![];
 
         let api1 = api.clone();
         services.push(
-            rosrust::service::<SubmitExtrinsic, _>(SUBMIT_SRV_NAME, move |req| {
+            rosrust::service::<SubmitExtrinsic, _>(&ros_name(namespace, SUBMIT_SRV_NAME), move |req| {
                 let mut res = SubmitExtrinsicRes::default();
                 match api1.submit_extrinsic(req.extrinsic.data.into()) {
                     Ok(hash) => {
@@ -114,9 +188,24 @@ impl<B, E, P, RA> RosRpc for Author<B, E, P, RA> where
             })?
         );
 
+        let api5 = api.clone();
+        services.push(
+            rosrust::service::<SubmitAndWatch, _>(&ros_name(namespace, SUBMIT_AND_WATCH_SRV_NAME), move |req| {
+                let mut res = SubmitAndWatchRes::default();
+                match api5.submit_and_watch(req.extrinsic.data.into()) {
+                    Ok((watch_id, hash)) => {
+                        res.watch_id = watch_id;
+                        res.hash.data = hash.into();
+                    },
+                    Err(err) => res.error = err.to_string()
+                }
+                Ok(res)
+            })?
+        );
+
         let api2 = api.clone();
         services.push(
-            rosrust::service::<PendingExtrinsics, _>(PENDING_SRV_NAME, move |_req| {
+            rosrust::service::<PendingExtrinsics, _>(&ros_name(namespace, PENDING_SRV_NAME), move |_req| {
                 let mut res = PendingExtrinsicsRes::default();
                 for xt in api2.pending_extrinsics() {
                     let mut xt_msg = RawExtrinsic::default();
@@ -129,7 +218,7 @@ impl<B, E, P, RA> RosRpc for Author<B, E, P, RA> where
 
         let api3 = api.clone();
         services.push(
-            rosrust::service::<RemoveExtrinsic, _>(REMOVE_SRV_NAME, move |req| {
+            rosrust::service::<RemoveExtrinsic, _>(&ros_name(namespace, REMOVE_SRV_NAME), move |req| {
                 let mut res = RemoveExtrinsicRes::default();
                 let hashes = req.extrinsics.iter().map(|h| h.data.into()).collect();
                 for xt in api3.remove_extrinsic(hashes) {
@@ -141,6 +230,110 @@ impl<B, E, P, RA> RosRpc for Author<B, E, P, RA> where
             })?
         );
 
+        let api4 = api.clone();
+        services.push(
+            rosrust::service::<EstimateFee, _>(&ros_name(namespace, ESTIMATE_FEE_SRV_NAME), move |req| {
+                let mut res = EstimateFeeRes::default();
+                match api4.estimate_fee(req.extrinsic.data.into()) {
+                    Ok(estimate) => {
+                        res.weight = estimate.weight;
+                        res.dispatch_class = format!("{:?}", estimate.class);
+                        res.fee = estimate.fee.to_string();
+                    },
+                    Err(err) => res.error = err.to_string()
+                }
+                Ok(res)
+            })?
+        );
+
         Ok(services)
     }
 }
+
+fn publish_status(
+    publisher: &rosrust::Publisher<TxStatus>,
+    watch_id: u64,
+    hash: H256,
+    status: &str,
+    block: Option<H256>,
+) {
+    let mut msg = TxStatus::default();
+    msg.watch_id = watch_id;
+    msg.hash.data = hash.into();
+    msg.status = status.to_string();
+    if let Some(block) = block {
+        msg.block.data = block.into();
+    }
+    if let Err(e) = publisher.send(msg) {
+        warn!("Unable to publish transaction status: {}", e);
+    }
+}
+
+/// Publish status updates of watched extrinsics into `author/tx_status` topic.
+///
+/// Topic is advertised once, so robot could subscribe before extrinsic submission and
+/// get all its statuses; topic is latched, so late subscriber gets the last status.
+/// Transaction pool reports inclusion into block only, so `InBlock` extrinsics are
+/// tracked until their block is finalized or retracted.
+pub fn start_tx_status<B, E, Block, RA>(
+    client: Arc<Client<B, E, Block, RA>>,
+    mut watchers: mpsc::UnboundedReceiver<TxWatch>,
+    namespace: &str,
+) -> Result<impl Future<Output=()>, Error> where
+    B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+    E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+    Block: traits::Block<Hash=H256>,
+    RA: Send + Sync + 'static,
+{
+    let mut publisher = rosrust::publish::<TxStatus>(&ros_name(namespace, TX_STATUS_TOPIC_NAME), QUEUE_SIZE)?;
+    publisher.set_latching(true);
+    let mut statuses = SelectAll::new();
+    let mut finality = client.finality_notification_stream();
+    // Extrinsic hash and block by watch id
+    let mut in_block: HashMap<u64, (H256, H256)> = HashMap::new();
+
+    Ok(future::poll_fn(move |cx| {
+        while let Poll::Ready(Some((watch_id, watcher))) = watchers.poll_next_unpin(cx) {
+            let hash = watcher.hash().clone();
+            statuses.push(watcher.into_stream().map(move |status| (watch_id, hash, status)).boxed());
+        }
+
+        while let Poll::Ready(Some((watch_id, hash, status))) = statuses.poll_next_unpin(cx) {
+            let (name, block, is_final) = match status {
+                Status::Future => ("Future", None, false),
+                Status::Ready => ("Ready", None, false),
+                Status::Broadcast(_) => ("Broadcast", None, false),
+                Status::Finalized(block) => {
+                    in_block.insert(watch_id, (hash, block));
+                    ("InBlock", Some(block), false)
+                },
+                Status::Usurped(_) => ("Usurped", None, true),
+                Status::Dropped => ("Dropped", None, true),
+                Status::Invalid => ("Invalid", None, true),
+            };
+            publish_status(&publisher, watch_id, hash, name, block);
+            if is_final {
+                in_block.remove(&watch_id);
+            }
+        }
+
+        while let Poll::Ready(Some(notification)) = finality.poll_next_unpin(cx) {
+            let finalized = *notification.header.number();
+            in_block.retain(|watch_id, (hash, block)| {
+                let status = match client.header(&generic::BlockId::hash(*block)) {
+                    Ok(Some(ref header)) if *header.number() > finalized => return true,
+                    // Block of extrinsic could be retracted, it's finalized only when canonical
+                    Ok(Some(header)) => match client.block_hash(*header.number()) {
+                        Ok(Some(canonical)) if canonical == *block => "Finalized",
+                        _ => "Retracted",
+                    },
+                    _ => "Dropped",
+                };
+                publish_status(&publisher, *watch_id, *hash, status, Some(*block));
+                false
+            });
+        }
+
+        Poll::Pending
+    }))
+}
@@ -18,10 +18,13 @@
 
 use std::sync::Arc;
 use client::{self, Client};
+use codec::Encode;
 use primitives::{H256, Blake2Hasher};
 use runtime_primitives::generic::{BlockId, SignedBlock};
-use runtime_primitives::traits::{Block as BlockT, Header};
+use runtime_primitives::traits::{Block as BlockT, Header, UniqueSaturatedInto};
+use msgs::ros_name;
 use msgs::substrate_ros_msgs::{
+    self, RawExtrinsic, DigestItem,
     GetBlock, GetBlockRes,
     GetBlockHash, GetBlockHashRes,
     GetBlockHeader, GetBlockHeaderRes,
@@ -31,11 +34,36 @@ use msgs::substrate_ros_msgs::{
 use rosrust::api::error::Error;
 use crate::traits::RosRpc;
 
-const BLOCK_SRV_NAME: &str = "/chain/block";
-const BLOCK_HASH_SRV_NAME: &str = "/chain/block_hash";
-const BLOCK_HEADER_SRV_NAME: &str = "/chain/block_header";
-const BEST_HEAD_SRV_NAME: &str = "/chain/best_head";
-const FINALIZED_HEAD_SRV_NAME: &str = "/chain/finalized_head";
+const BLOCK_SRV_NAME: &str = "chain/block";
+const BLOCK_HASH_SRV_NAME: &str = "chain/block_hash";
+const BLOCK_HEADER_SRV_NAME: &str = "chain/block_header";
+const BEST_HEAD_SRV_NAME: &str = "chain/best_head";
+const FINALIZED_HEAD_SRV_NAME: &str = "chain/finalized_head";
+
+/// Convert block header into ROS message.
+pub fn header_msg<H: Header<Hash=H256>>(header: &H) -> substrate_ros_msgs::Header {
+    let mut msg = substrate_ros_msgs::Header::default();
+    msg.hash.data = header.hash().into();
+    msg.parent_hash.data = (*header.parent_hash()).into();
+    msg.number = (*header.number()).unique_saturated_into();
+    msg.state_root = (*header.state_root()).into();
+    msg.extrinsics_root = (*header.extrinsics_root()).into();
+    msg.digest = header.digest().logs().iter()
+        .map(|item| DigestItem { data: item.encode() })
+        .collect();
+    msg
+}
+
+/// Convert signed block into ROS message, justification is empty when not given.
+pub fn block_msg<Block: BlockT<Hash=H256>>(signed: &SignedBlock<Block>) -> substrate_ros_msgs::Block {
+    let mut msg = substrate_ros_msgs::Block::default();
+    msg.header = header_msg(signed.block.header());
+    msg.extrinsics = signed.block.extrinsics().iter()
+        .map(|xt| RawExtrinsic { data: xt.encode() })
+        .collect();
+    msg.justification = signed.justification.clone().unwrap_or_default();
+    msg
+}
 
 /// Chain API
 pub struct Chain<B, E, Block: BlockT, RA> {
@@ -95,40 +123,46 @@ impl<B, E, Block: BlockT, RA> RosRpc for Chain<B, E, Block, RA> where
     E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
     RA: Send + Sync + 'static
 {
-    fn start(api: Arc<Self>) -> Result<Vec<rosrust::Service>, Error> {
+    fn start(api: Arc<Self>, namespace: &str) -> Result<Vec<rosrust::Service>, Error> {
         let mut services = vec![];
 
         let api1 = api.clone();
         services.push(
-            rosrust::service::<GetBlockHeader, _>(BLOCK_HEADER_SRV_NAME, move |req| {
+            rosrust::service::<GetBlockHeader, _>(&ros_name(namespace, BLOCK_HEADER_SRV_NAME), move |req| {
                 let mut res = GetBlockHeaderRes::default(); 
                 let hash = if req.hash.data == [0; 32] {
                     None
                 } else {
                     Some(req.hash.data.into())
                 };
-                res.header_json = serde_json::to_string(&api1.header(hash)).unwrap();
+                match api1.header(hash) {
+                    Some(header) => res.header = header_msg(&header),
+                    None => res.error = "Block header not found".to_string(),
+                }
                 Ok(res)
             })?
         );
 
         let api2 = api.clone();
         services.push(
-            rosrust::service::<GetBlock, _>(BLOCK_SRV_NAME, move |req| {
+            rosrust::service::<GetBlock, _>(&ros_name(namespace, BLOCK_SRV_NAME), move |req| {
                 let mut res = GetBlockRes::default();
                 let hash = if req.hash.data == [0; 32] {
                     None
                 } else {
                     Some(req.hash.data.into())
                 };
-                res.block_json = serde_json::to_string(&api2.block(hash)).unwrap();
+                match api2.block(hash) {
+                    Some(block) => res.block = block_msg(&block),
+                    None => res.error = "Block not found".to_string(),
+                }
                 Ok(res)
             })?
         );
 
         let api3 = api.clone();
         services.push(
-            rosrust::service::<GetBlockHash, _>(BLOCK_HASH_SRV_NAME, move |req| {
+            rosrust::service::<GetBlockHash, _>(&ros_name(namespace, BLOCK_HASH_SRV_NAME), move |req| {
                 let mut res = GetBlockHashRes::default();
                 res.hash.data = api3.block_hash(Some(req.number)).unwrap().into();
                 Ok(res)
@@ -137,7 +171,7 @@ impl<B, E, Block: BlockT, RA> RosRpc for Chain<B, E, Block, RA> where
 
         let api4 = api.clone();
         services.push(
-            rosrust::service::<GetBestHead, _>(BEST_HEAD_SRV_NAME, move |_| {
+            rosrust::service::<GetBestHead, _>(&ros_name(namespace, BEST_HEAD_SRV_NAME), move |_| {
                 let mut res = GetBestHeadRes::default();
                 res.hash.data = api4.block_hash(None).unwrap().into();
                 Ok(res)
@@ -146,7 +180,7 @@ impl<B, E, Block: BlockT, RA> RosRpc for Chain<B, E, Block, RA> where
 
         let api5 = api.clone();
         services.push(
-            rosrust::service::<GetFinalizedHead, _>(FINALIZED_HEAD_SRV_NAME, move |_| {
+            rosrust::service::<GetFinalizedHead, _>(&ros_name(namespace, FINALIZED_HEAD_SRV_NAME), move |_| {
                 let mut res = GetFinalizedHeadRes::default();
                 res.hash.data = api5.finalized_head().into();
                 Ok(res)
@@ -0,0 +1,71 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2018-2019 Airalab <research@aira.life> 
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+///! Development chain API in ROS namespace.
+
+use std::sync::Arc;
+use futures::channel::mpsc;
+use rosrust::api::error::Error;
+use msgs::ros_name;
+use msgs::std_srvs::{Trigger, TriggerRes};
+use crate::traits::RosRpc;
+
+const SEAL_BLOCK_SRV_NAME: &str = "dev/seal_block";
+
+/// Development API: manual block sealing.
+pub struct Dev {
+    /// Block sealing trigger
+    seal: mpsc::UnboundedSender<()>,
+}
+
+impl Dev {
+    /// Create new instance of development API.
+    pub fn new(seal: mpsc::UnboundedSender<()>) -> Self {
+        Dev { seal }
+    }
+
+    fn seal_block(&self) -> Result<(), &str> {
+        self.seal.unbounded_send(()).map_err(|_| "Sealing engine is stopped")
+    }
+}
+
+impl RosRpc for Dev {
+    fn start(api: Arc<Self>, namespace: &str) -> Result<Vec<rosrust::Service>, Error> {
+        let mut services = vec![];
+
+        services.push(
+            rosrust::service::<Trigger, _>(&ros_name(namespace, SEAL_BLOCK_SRV_NAME), move |_| {
+                let mut res = TriggerRes::default();
+                match api.seal_block() {
+                    Ok(()) => res.success = true,
+                    Err(err) => res.message = err.to_string(),
+                }
+                Ok(res)
+            })?
+        );
+
+        Ok(services)
+    }
+}
+
+/// Launch development API services in given namespace.
+pub fn start_dev_services(
+    seal: mpsc::UnboundedSender<()>,
+    namespace: &str,
+) -> Result<Vec<rosrust::Service>, Error> {
+    Dev::start(Arc::new(Dev::new(seal)), namespace)
+}
@@ -20,23 +20,39 @@
 use network::{specialization::NetworkSpecialization, NetworkService, ExHashT};
 use transaction_pool::txpool::{ChainApi as PoolChainApi, Pool};
 pub use substrate_rpc::system::helpers::SystemInfo;
-use runtime_primitives::traits::Block;
+use runtime_primitives::traits::{Block, ProvideRuntimeApi};
+use substrate_ros_api::FeeApi;
 use primitives::{Blake2Hasher, H256};
 use rosrust::api::error::Error;
-use std::sync::Arc;
+use std::{fmt::Display, sync::Arc};
+use futures::{prelude::*, channel::mpsc, future};
 use client::Client;
+use codec::Codec;
+use crate::traits::RosRpc;
 
 pub mod traits;
 pub mod system;
 pub mod author;
 pub mod chain;
 pub mod state;
+pub mod dev;
 
-pub fn start_rpc<B, S, H, F, E, P, A>(
+/// Initialize ROS node, it's shared by all ROS APIs of the process.
+pub fn init(node_name: &str) -> Result<(), Error> {
+    if !rosrust::is_initialized() {
+        rosrust::try_init_with_options(node_name, false)?;
+    }
+    Ok(())
+}
+
+/// Launch Substrate API services and publishers in given namespace.
+pub fn start_rpc<B, S, H, F, E, P, A, Balance>(
     system_info: SystemInfo,
     service_network: Arc<NetworkService<B, S, H>>,
     service_client: Arc<Client<F, E, <P as PoolChainApi>::Block, A>>,
-    service_transaction_pool: Arc<Pool<P>>
+    service_transaction_pool: Arc<Pool<P>>,
+    remote_storage: Option<Arc<dyn state::RemoteStorage<<P as PoolChainApi>::Block>>>,
+    namespace: &str,
 ) -> Result<(Vec<rosrust::Service>, impl Future<Output=()>), Error> where
     B: Block<Hash=H256>,
     S: NetworkSpecialization<B>,
@@ -46,7 +62,10 @@ pub fn start_rpc<B, S, H, F, E, P, A>(
     P: PoolChainApi<Hash=H256> + Sync + Send + 'static,
     P::Block: Block<Hash=H256>,
     P::Error: 'static,
-    A: Send + Sync + 'static
+    A: Send + Sync + 'static,
+    Client<F, E, <P as PoolChainApi>::Block, A>: ProvideRuntimeApi,
+    <Client<F, E, <P as PoolChainApi>::Block, A> as ProvideRuntimeApi>::Api: FeeApi<<P as PoolChainApi>::Block, Balance>,
+    Balance: Codec + Display + Send + Sync + 'static,
 {
 
     let system = Arc::new(system::System::new(
@@ -54,26 +73,36 @@ pub fn start_rpc<B, S, H, F, E, P, A>(
         service_network,
     ));
 
+    let (watchers_tx, watchers_rx) = mpsc::unbounded();
     let author = Arc::new(author::Author::new(
         service_client.clone(),
         service_transaction_pool,
+        watchers_tx,
     ));
+    let tx_status = author::start_tx_status(service_client.clone(), watchers_rx, namespace)?;
 
     let chain = Arc::new(chain::Chain::new(
         service_client.clone(),
     ));
 
+    let (subscriptions_tx, subscriptions_rx) = mpsc::unbounded();
     let state = Arc::new(state::State::new(
         service_client,
+        remote_storage,
+        subscriptions_tx,
     ));
 
-    let task = system.start_publishers()?;
+    let task = future::join3(
+        system::start_publishers(system.clone(), namespace)?,
+        tx_status,
+        state::start_storage_subscriptions(subscriptions_rx),
+    ).map(|_| ());
 
     let services = [
-        system.start_services()?,
-        author.start_services()?,
-        state.start_services()?,
-        chain.start_services()?,
+        RosRpc::start(system, namespace)?,
+        RosRpc::start(author, namespace)?,
+        RosRpc::start(state, namespace)?,
+        RosRpc::start(chain, namespace)?,
     ].concat();
 
     Ok((services, task))
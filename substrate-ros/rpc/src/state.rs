@@ -16,35 +16,90 @@
 //
 ///////////////////////////////////////////////////////////////////////////////
 
-use std::sync::Arc;
-use client::{self, Client};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}},
+    time::Duration,
+};
+use log::warn;
+use futures01::IntoFuture;
+use futures::{
+    StreamExt, FutureExt, channel::{mpsc, oneshot}, executor, compat::Future01CompatExt,
+    future::{self as future03, BoxFuture, Either},
+};
+use futures_timer::Delay;
+use client::{self, Client, BlockchainEvents, light::fetcher::{Fetcher, RemoteReadRequest}};
 use primitives::storage::{self, StorageData};
-use primitives::{H256, Blake2Hasher};
+use primitives::{H256, Blake2Hasher, blake2_256};
 use runtime_primitives::{
-    generic::BlockId, traits::{Block as BlockT}
+    generic::BlockId, traits::{Block as BlockT, Header as HeaderT}
 };
 use state_machine::{self, ExecutionStrategy};
+use msgs::ros_name;
 use msgs::substrate_ros_msgs::{
-    BlockHash, StorageKey,
+    BlockHash, StorageKey, StorageChangeSet,
     StateCall, StateCallRes,
     StorageHash, StorageHashRes,
     StorageKeys, StorageKeysRes,
     StorageQuery, StorageQueryRes,
     StorageSize, StorageSizeRes,
+    SubscribeStorage, SubscribeStorageRes,
+    UnsubscribeStorage, UnsubscribeStorageRes,
 };
 use rosrust::api::error::Error;
 use crate::traits::RosRpc;
 
-const CALL_SRV_NAME: &str = "/state/call";
-const KEYS_SRV_NAME: &str = "/state/keys";
-const QUERY_SRV_NAME: &str = "/state/query";
-const HASH_SRV_NAME: &str = "/state/hash";
-const SIZE_SRV_NAME: &str = "/state/size";
+const CALL_SRV_NAME: &str = "state/call";
+const KEYS_SRV_NAME: &str = "state/keys";
+const QUERY_SRV_NAME: &str = "state/query";
+const HASH_SRV_NAME: &str = "state/hash";
+const SIZE_SRV_NAME: &str = "state/size";
+const SUBSCRIBE_STORAGE_SRV_NAME: &str = "state/subscribe_storage";
+const UNSUBSCRIBE_STORAGE_SRV_NAME: &str = "state/unsubscribe_storage";
+const STORAGE_TOPIC_PREFIX: &str = "state/storage";
+
+/// ROS Pub/Sub queue size.
+/// http://wiki.ros.org/roscpp/Overview/Publishers%20and%20Subscribers#Queueing_and_Lazy_Deserialization
+const QUEUE_SIZE: usize = 10;
+
+/// Maximum number of active storage subscriptions.
+const MAX_STORAGE_SUBSCRIPTIONS: usize = 64;
+
+/// Time to wait for storage value requested from full node.
+const REMOTE_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Publisher task of storage subscription.
+pub type StorageSubscription = BoxFuture<'static, ()>;
+
+/// Storage reader of light client, data is requested from full nodes.
+pub trait RemoteStorage<Block: BlockT>: Send + Sync {
+    /// Read storage value at given block.
+    fn remote_storage(&self, header: Block::Header, key: Vec<u8>) -> Result<Option<Vec<u8>>, String>;
+}
+
+impl<Block: BlockT, F: Fetcher<Block>> RemoteStorage<Block> for F {
+    fn remote_storage(&self, header: Block::Header, key: Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+        let request = RemoteReadRequest { block: header.hash(), header, key, retry_count: None };
+        let read = Box::pin(self.remote_read(request).into_future().compat());
+        match executor::block_on(future03::select(read, Delay::new(REMOTE_READ_TIMEOUT))) {
+            Either::Left((result, _)) => result.map_err(|e| format!("{:?}", e)),
+            Either::Right(_) => Err("Remote storage request timed out".into()),
+        }
+    }
+}
 
 /// Chain API
 pub struct State<B, E, Block: BlockT, RA> {
     /// Substrate client
     client: Arc<Client<B, E, Block, RA>>,
+    /// Remote storage reader, used by light client
+    remote: Option<Arc<dyn RemoteStorage<Block>>>,
+    /// Storage subscription publishers
+    subscriptions: mpsc::UnboundedSender<StorageSubscription>,
+    /// Cancel handles of active storage subscriptions by topic
+    active_subscriptions: Mutex<HashMap<String, oneshot::Sender<()>>>,
+    /// Next storage subscription id
+    next_subscription_id: AtomicU64,
 }
 
 impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
@@ -53,12 +108,18 @@ impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
     E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
     RA: Send + Sync + 'static
 {
-    /// Create new instance of Authoring API.
+    /// Create new instance of State API.
     pub fn new(
         client: Arc<Client<B, E, Block, RA>>,
+        remote: Option<Arc<dyn RemoteStorage<Block>>>,
+        subscriptions: mpsc::UnboundedSender<StorageSubscription>,
     ) -> Self {
         State {
             client,
+            remote,
+            subscriptions,
+            active_subscriptions: Mutex::new(HashMap::new()),
+            next_subscription_id: AtomicU64::new(0),
         }
     }
 
@@ -83,6 +144,9 @@ impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
 	fn storage_keys(&self, key_prefix: storage::StorageKey, block: Option<Block::Hash>)
         -> Result<Vec<storage::StorageKey>, String>
     {
+        if self.remote.is_some() {
+            return Err("Storage keys isn't available on light client".to_owned());
+        }
 		self.client
             .storage_keys(&BlockId::Hash(self.unwrap_or_best(block)), &key_prefix)
             .map_err(|_| "Storage keys error".to_owned())
@@ -91,6 +155,13 @@ impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
 	fn storage(&self, key: storage::StorageKey, block: Option<Block::Hash>)
         -> Result<Option<StorageData>, String>
     {
+        if let Some(ref remote) = self.remote {
+            let block = BlockId::Hash(self.unwrap_or_best(block));
+            let header = self.client.header(&block)
+                .map_err(|_| "Storage query error".to_owned())?
+                .ok_or_else(|| "Block not found".to_owned())?;
+            return remote.remote_storage(header, key.0).map(|data| data.map(StorageData));
+        }
 		self.client
             .storage(&BlockId::Hash(self.unwrap_or_best(block)), &key)
             .map_err(|_| "Storage query error".to_owned())
@@ -99,6 +170,10 @@ impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
 	fn storage_hash(&self, key: storage::StorageKey, block: Option<Block::Hash>)
         -> Result<Option<Block::Hash>, String>
     {
+        if self.remote.is_some() {
+            return self.storage(key, block)
+                .map(|data| data.map(|d| H256::from(blake2_256(&d.0))));
+        }
 		self.client
             .storage_hash(&BlockId::Hash(self.unwrap_or_best(block)), &key)
             .map_err(|_| "Storage hash error".to_owned())
@@ -111,6 +186,67 @@ impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
             .map_err(|_| "Storage size error".to_owned())
             .map(|x| x.map(|d| d.0.len() as u64))
 	}
+
+    /// Publish changes of given keys into new topic, returns topic name.
+    fn subscribe_storage(&self, keys: Vec<storage::StorageKey>, namespace: &str)
+        -> Result<String, String>
+    {
+        if self.remote.is_some() {
+            return Err("Storage subscription isn't available on light client".to_owned());
+        }
+        let mut active = self.active_subscriptions.lock()
+            .map_err(|_| "Storage subscription error".to_owned())?;
+        // Subscriptions closed by publisher task itself are cleaned up here
+        active.retain(|_, cancel| !cancel.is_canceled());
+        if active.len() >= MAX_STORAGE_SUBSCRIPTIONS {
+            return Err("Too many storage subscriptions".to_owned());
+        }
+
+        let changes = self.client.storage_changes_notification_stream(Some(&keys), None)
+            .map_err(|_| "Storage subscription error".to_owned())?;
+
+        let id = self.next_subscription_id.fetch_add(1, Ordering::SeqCst);
+        let topic = ros_name(namespace, &format!("{}/{}", STORAGE_TOPIC_PREFIX, id));
+        let publisher = rosrust::publish::<StorageChangeSet>(&topic, QUEUE_SIZE)
+            .map_err(|e| e.to_string())?;
+
+        let task = changes.for_each(move |(block, changeset)| {
+            for (_, key, value) in changeset.iter() {
+                let mut msg = StorageChangeSet::default();
+                msg.block.data = block.into();
+                msg.key.data = key.0.clone();
+                msg.removed = value.is_none();
+                msg.value = value.map(|v| v.0.clone()).unwrap_or_default();
+                if let Err(e) = publisher.send(msg) {
+                    warn!("Unable to publish storage change set: {}", e);
+                }
+            }
+            future03::ready(())
+        });
+        let (cancel, canceled) = oneshot::channel();
+        let task = future03::select(Box::pin(task), canceled).map(|_| ());
+        self.subscriptions.unbounded_send(task.boxed())
+            .map_err(|_| "Storage subscription publisher is stopped".to_owned())?;
+        active.insert(topic.clone(), cancel);
+        Ok(topic)
+    }
+
+    /// Stop publishing into topic of storage subscription.
+    fn unsubscribe_storage(&self, topic: &str) -> Result<(), String> {
+        let mut active = self.active_subscriptions.lock()
+            .map_err(|_| "Storage subscription error".to_owned())?;
+        match active.remove(topic) {
+            Some(cancel) => { let _ = cancel.send(()); Ok(()) },
+            None => Err("Storage subscription not found".to_owned()),
+        }
+    }
+}
+
+/// Run publishers of storage subscriptions made by `state/subscribe_storage` service.
+pub fn start_storage_subscriptions(
+    subscriptions: mpsc::UnboundedReceiver<StorageSubscription>,
+) -> impl futures::Future<Output=()> {
+    subscriptions.for_each_concurrent(None, |task| task)
 }
 
 fn unwrap_hash<Block>(block_msg: BlockHash) -> Option<Block::Hash> where
@@ -129,12 +265,12 @@ impl<B, E, Block: BlockT, RA> RosRpc for State<B, E, Block, RA> where
     E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
     RA: Send + Sync + 'static
 {
-    fn start(api: Arc<Self>) -> Result<Vec<rosrust::Service>, Error> {
+    fn start(api: Arc<Self>, namespace: &str) -> Result<Vec<rosrust::Service>, Error> {
         let mut services = vec![];
 
         let api1 = api.clone();
         services.push(
-            rosrust::service::<StateCall, _>(CALL_SRV_NAME, move |req| {
+            rosrust::service::<StateCall, _>(&ros_name(namespace, CALL_SRV_NAME), move |req| {
                 let mut res = StateCallRes::default(); 
                 let block = unwrap_hash::<Block>(req.block);
                 match api1.call(req.method, req.data, block) {
@@ -150,7 +286,7 @@ impl<B, E, Block: BlockT, RA> RosRpc for State<B, E, Block, RA> where
 
         let api2 = api.clone();
         services.push(
-            rosrust::service::<StorageQuery, _>(QUERY_SRV_NAME, move |req| {
+            rosrust::service::<StorageQuery, _>(&ros_name(namespace, QUERY_SRV_NAME), move |req| {
                 let mut res = StorageQueryRes::default(); 
                 let block = unwrap_hash::<Block>(req.block);
                 match api2.storage(storage::StorageKey(req.key.data), block) {
@@ -166,7 +302,7 @@ impl<B, E, Block: BlockT, RA> RosRpc for State<B, E, Block, RA> where
 
         let api3 = api.clone();
         services.push(
-            rosrust::service::<StorageSize, _>(SIZE_SRV_NAME, move |req| {
+            rosrust::service::<StorageSize, _>(&ros_name(namespace, SIZE_SRV_NAME), move |req| {
                 let mut res = StorageSizeRes::default(); 
                 let block = unwrap_hash::<Block>(req.block);
                 match api3.storage_size(storage::StorageKey(req.key.data), block) {
@@ -182,7 +318,7 @@ impl<B, E, Block: BlockT, RA> RosRpc for State<B, E, Block, RA> where
 
         let api4 = api.clone();
         services.push(
-            rosrust::service::<StorageHash, _>(HASH_SRV_NAME, move |req| {
+            rosrust::service::<StorageHash, _>(&ros_name(namespace, HASH_SRV_NAME), move |req| {
                 let mut res = StorageHashRes::default(); 
                 let block = unwrap_hash::<Block>(req.block);
                 match api4.storage_hash(storage::StorageKey(req.key.data), block) {
@@ -198,7 +334,7 @@ impl<B, E, Block: BlockT, RA> RosRpc for State<B, E, Block, RA> where
 
         let api5 = api.clone();
         services.push(
-            rosrust::service::<StorageKeys, _>(KEYS_SRV_NAME, move |req| {
+            rosrust::service::<StorageKeys, _>(&ros_name(namespace, KEYS_SRV_NAME), move |req| {
                 let mut res = StorageKeysRes::default(); 
                 let block = unwrap_hash::<Block>(req.block);
                 match api5.storage_keys(storage::StorageKey(req.prefix.data), block) {
@@ -214,6 +350,32 @@ impl<B, E, Block: BlockT, RA> RosRpc for State<B, E, Block, RA> where
             })?
         );
 
+        let api6 = api.clone();
+        let ns = namespace.to_owned();
+        services.push(
+            rosrust::service::<SubscribeStorage, _>(&ros_name(namespace, SUBSCRIBE_STORAGE_SRV_NAME), move |req| {
+                let mut res = SubscribeStorageRes::default();
+                let keys = req.keys.into_iter().map(|key| storage::StorageKey(key.data)).collect();
+                match api6.subscribe_storage(keys, &ns) {
+                    Ok(topic) => { res.topic = topic; },
+                    Err(e) => { res.error = e; }
+                }
+                Ok(res)
+            })?
+        );
+
+        let api7 = api.clone();
+        services.push(
+            rosrust::service::<UnsubscribeStorage, _>(&ros_name(namespace, UNSUBSCRIBE_STORAGE_SRV_NAME), move |req| {
+                let mut res = UnsubscribeStorageRes::default();
+                match api7.unsubscribe_storage(&req.topic) {
+                    Ok(()) => { res.success = true; },
+                    Err(e) => { res.error = e; }
+                }
+                Ok(res)
+            })?
+        );
+
         Ok(services)
     }
 }
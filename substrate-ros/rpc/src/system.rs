@@ -27,6 +27,7 @@ use network::{
     NetworkService, ExHashT,
 };
 use msgs::{
+    ros_name,
     std_srvs::{Trigger, TriggerRes},
     substrate_ros_msgs::{SystemHealth, SystemHealthRes, SystemHealthInfo},
     std_msgs
@@ -37,11 +38,11 @@ use futures::prelude::*;
 
 pub use substrate_rpc::system::helpers::SystemInfo;
 
-const SYSTEM_NAME_SRV_NAME: &str = "/system/name";
-const SYSTEM_VERSION_SRV_NAME: &str = "/system/version";
-const SYSTEM_CHAIN_SRV_NAME: &str = "/system/chain_name";
-const SYSTEM_PROPERTIES_SRV_NAME: &str = "/system/properties";
-const SYSTEM_HEALTH_SRV_NAME: &str = "/system/health";
+const SYSTEM_NAME_SRV_NAME: &str = "system/name";
+const SYSTEM_VERSION_SRV_NAME: &str = "system/version";
+const SYSTEM_CHAIN_SRV_NAME: &str = "system/chain_name";
+const SYSTEM_PROPERTIES_SRV_NAME: &str = "system/properties";
+const SYSTEM_HEALTH_SRV_NAME: &str = "system/health";
 
 /// ROS Pub/Sub queue size.
 /// http://wiki.ros.org/roscpp/Overview/Publishers%20and%20Subscribers#Queueing_and_Lazy_Deserialization
@@ -151,12 +152,12 @@ impl<B: traits::Block, S, H> RosRpc for System<B, S, H> where
     S: NetworkSpecialization<B>,
     H: ExHashT 
 {
-    fn start(api: Arc<Self>) -> Result<Vec<rosrust::Service>, Error> {
+    fn start(api: Arc<Self>, namespace: &str) -> Result<Vec<rosrust::Service>, Error> {
         let mut services = vec![];
 
         let api1 = api.clone();
         services.push(
-            rosrust::service::<Trigger, _>(SYSTEM_NAME_SRV_NAME, move |_| {
+            rosrust::service::<Trigger, _>(&ros_name(namespace, SYSTEM_NAME_SRV_NAME), move |_| {
                 let mut res = TriggerRes::default();
                 res.success = true;
                 res.message = api1.system_name(); 
@@ -166,7 +167,7 @@ impl<B: traits::Block, S, H> RosRpc for System<B, S, H> where
 
         let api2 = api.clone();
         services.push(
-            rosrust::service::<Trigger, _>(SYSTEM_VERSION_SRV_NAME, move |_| {
+            rosrust::service::<Trigger, _>(&ros_name(namespace, SYSTEM_VERSION_SRV_NAME), move |_| {
                 let mut res = TriggerRes::default();
                 res.success = true;
                 res.message = api2.system_version(); 
@@ -176,7 +177,7 @@ impl<B: traits::Block, S, H> RosRpc for System<B, S, H> where
 
         let api3 = api.clone();
         services.push(
-            rosrust::service::<Trigger, _>(SYSTEM_CHAIN_SRV_NAME, move |_| {
+            rosrust::service::<Trigger, _>(&ros_name(namespace, SYSTEM_CHAIN_SRV_NAME), move |_| {
                 let mut res = TriggerRes::default();
                 res.success = true;
                 res.message = api3.system_chain();
@@ -186,7 +187,7 @@ impl<B: traits::Block, S, H> RosRpc for System<B, S, H> where
 
         let api4 = api.clone();
         services.push(
-            rosrust::service::<Trigger, _>(SYSTEM_PROPERTIES_SRV_NAME, move |_| {
+            rosrust::service::<Trigger, _>(&ros_name(namespace, SYSTEM_PROPERTIES_SRV_NAME), move |_| {
                 let mut res = TriggerRes::default();
                 res.success = true;
                 res.message = serde_json::to_string(&api4.system_properties()).unwrap();
@@ -196,7 +197,7 @@ impl<B: traits::Block, S, H> RosRpc for System<B, S, H> where
 
         let api5 = api.clone();
         services.push(
-            rosrust::service::<SystemHealth, _>(SYSTEM_HEALTH_SRV_NAME, move |_| {
+            rosrust::service::<SystemHealth, _>(&ros_name(namespace, SYSTEM_HEALTH_SRV_NAME), move |_| {
                 let mut res = SystemHealthRes::default();
                 let health = api5.system_health();
                 res.info.peers = health.peers as u32;
@@ -210,21 +211,22 @@ impl<B: traits::Block, S, H> RosRpc for System<B, S, H> where
 }
 
 pub fn start_publishers<B: traits::Block, S, H>(
-    api: Arc<System<B, S, H>>
+    api: Arc<System<B, S, H>>,
+    namespace: &str,
 ) -> Result<impl Future<Output=()>, Error> where
     S: NetworkSpecialization<B>,
     H: ExHashT 
 {
-    let name_pub = rosrust::publish(SYSTEM_NAME_SRV_NAME, QUEUE_SIZE)?;
+    let name_pub = rosrust::publish(&ros_name(namespace, SYSTEM_NAME_SRV_NAME), QUEUE_SIZE)?;
     let name_future = publish_system_name(api.clone(), name_pub);
 
-    let version_pub = rosrust::publish(SYSTEM_VERSION_SRV_NAME, QUEUE_SIZE)?;
+    let version_pub = rosrust::publish(&ros_name(namespace, SYSTEM_VERSION_SRV_NAME), QUEUE_SIZE)?;
     let version = publish_system_version(api.clone(), version_pub); 
 
-    let chain_pub = rosrust::publish(SYSTEM_CHAIN_SRV_NAME, QUEUE_SIZE)?;
+    let chain_pub = rosrust::publish(&ros_name(namespace, SYSTEM_CHAIN_SRV_NAME), QUEUE_SIZE)?;
     let chain = publish_system_chain(api.clone(), chain_pub);
 
-    let health_pub = rosrust::publish(SYSTEM_HEALTH_SRV_NAME, QUEUE_SIZE)?;
+    let health_pub = rosrust::publish(&ros_name(namespace, SYSTEM_HEALTH_SRV_NAME), QUEUE_SIZE)?;
     let health = publish_system_health(api.clone(), health_pub);
 
     let task = future::join4(name, version, chain, health).map(|_| ());
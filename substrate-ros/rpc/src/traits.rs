@@ -21,6 +21,6 @@ use std::sync::Arc;
 
 ///! Simple ROS RPC launcher trait
 pub trait RosRpc {
-    ///! Launch ROS RPC services
-    fn start(api: Arc<Self>) -> Result<Vec<rosrust::Service>, Error>;
+    ///! Launch ROS RPC services in given namespace
+    fn start(api: Arc<Self>, namespace: &str) -> Result<Vec<rosrust::Service>, Error>;
 }